@@ -10,6 +10,10 @@ class Bullet:
         self.texture = None
         self.shouldDelete = False
         self.damage = 0
+        self.rotation = 0.0
+        self.hitbox = None
+        self.color = "white"
+        self.destroyOnHit = None
         
         self.state = "start"
         self.timer = 0.0
@@ -38,6 +42,27 @@ class Bullet:
         self.vx += speed * math.cos(rad)
         self.vy += speed * math.sin(rad)
 
+    def setRotation(self, angleDeg):
+        self.rotation = angleDeg
+
+    def setColor(self, name):
+        self.color = name
+
+    def setDestroyOnHit(self, value=True):
+        self.destroyOnHit = value
+
+    def setHitboxCircle(self, radius):
+        self.hitbox = {"type": "circle", "radius": radius}
+
+    def setHitboxRect(self, width, height, angleDeg=0.0):
+        self.hitbox = {"type": "rect", "width": width, "height": height, "angle": angleDeg}
+
+    def setHitboxCapsule(self, length, radius, angleDeg=0.0):
+        self.hitbox = {"type": "capsule", "length": length, "radius": radius, "angle": angleDeg}
+
+    def setHitboxMask(self, threshold=0.5):
+        self.hitbox = {"type": "mask", "threshold": threshold}
+
     def setPos(self, x, y):
         self.x = x
         self.y = y
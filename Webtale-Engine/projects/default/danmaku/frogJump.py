@@ -5,6 +5,14 @@ def init():
     return {
         "textureWait": "texture/enemy/spr_frogbullet_stop.png",
         "box": [217.0, 125.0, 417.0, 385.0],
+        "soul": [317.0, 255.0],
+        "duration": 6.0,
+        "endWhenClear": True,
+        "schedule": [
+            [0.0, {}],
+            [1.5, {"offset": 30.0}],
+            [3.0, {"offset": 60.0}]
+        ],
         "rustSim": {
             "update": [
                 ["timer", "timer - dt"],
@@ -20,12 +28,14 @@ def init():
         }
     }
 
-def spawn():
+def spawn(params):
     grav_speed = 0.4 * 30.0 * 30.0
     gx = grav_speed * math.cos(math.radians(280.0))
     gy = grav_speed * math.sin(math.radians(280.0))
     return {
         "vars": {
+            "x": params["x"] - params.get("offset", 0.0),
+            "y": params["y"],
             "state": 0.0,
             "timer": 0.5 + random.random() * 0.5,
             "did_jump": 0.0,
@@ -36,5 +46,6 @@ def spawn():
             "jump_speed": (7.0 + random.random() * 3.0) * 30.0,
             "jump_angle": 145.0 - random.random() * 20.0
         },
-        "damage": 4
+        "damage": 4,
+        "hitbox": {"type": "mask"}
     }
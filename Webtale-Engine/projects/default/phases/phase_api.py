@@ -11,7 +11,11 @@ def reset(context):
         "bubbleMessage": None,
         "bubbleTexture": None,
         "bubblePosition": None,
+        "spareable": None,
+        "fleeable": None,
         "nextPhase": None,
+        "soulMode": None,
+        "soulGravity": None,
     }
 
 def getContext():
@@ -26,6 +30,18 @@ def getLastPlayerAction():
 def getLastActCommand():
     return _context.get("lastActCommand")
 
+def getVar(name, default=None):
+    return _context.setdefault("vars", {}).get(name, default)
+
+def setVar(name, value):
+    _context.setdefault("vars", {})[name] = value
+
+def getBattleVar(name, default=None):
+    return _context.setdefault("battleVars", {}).get(name, default)
+
+def setBattleVar(name, value):
+    _context.setdefault("battleVars", {})[name] = value
+
 def setDialogText(text):
     _state["dialogText"] = text
 
@@ -44,9 +60,26 @@ def setBubbleTexture(path):
 def setBubblePosition(x, y):
     _state["bubblePosition"] = [x, y]
 
+def isSpareable():
+    return _context.get("spareable", False)
+
+def setSpareable(value=True):
+    _state["spareable"] = bool(value)
+
+def isFleeable():
+    return _context.get("fleeable", True)
+
+def setFleeable(value=True):
+    _state["fleeable"] = bool(value)
+
 def setNextPhase(name):
     _state["nextPhase"] = name
 
+def setSoulMode(mode, gravity=None):
+    _state["soulMode"] = mode
+    if gravity is not None:
+        _state["soulGravity"] = gravity
+
 def getState():
     return {key: value for key, value in _state.items() if value is not None}
 
@@ -0,0 +1,19 @@
+def getProject():
+    return {
+        "name": "Default",
+        "author": "Webtale Engine",
+        "engineVersion": "0.1.0",
+        "entry": {
+            "player": "properties/playerStatus.py",
+            "enemy": "properties/enemyStatus.py",
+            "item": "properties/item.py",
+            "initialPhase": "phase1"
+        },
+        "assetRoots": ["assets"],
+        "fonts": {
+            "main": "font/Mars_Needs_Cunnilingus.ttf",
+            "dialog": "font/8bitOperatorPlus-Bold.ttf",
+            "hpLabel": "font/8-BIT_WO.ttf",
+            "damage": "font/hachicro.TTF"
+        }
+    }
@@ -5,6 +5,8 @@ def getEnemyStatus():
         "enemyMaxHp": 30,
         "enemyAtk": 4,
         "enemyDef": 5,
+        "enemyExp": 3,
+        "enemyGold": 2,
         "dialogText": "* Froggit hops close!",
         "phaseScript": "PhaseExample",
         "attackPatterns": ["frogJump"],
@@ -14,6 +16,7 @@ def getEnemyStatus():
             "Compliment": "* Froggit didn't understand what you said,\n  but was flattered anyway.",
             "Threaten": "* Froggit didn't understand what you said,\n  but was scared anyway."
         },
+        "spareActs": {"Compliment": 1, "Threaten": 1},
         "bubbleMessages": ["Ribbit, ribbit.", "Croak.", "Hop, hop."],
         "bodyTexture": "texture/enemy/spr_froglegs_0.png",
         "headTexture": "texture/enemy/spr_froghead_0.png",
@@ -1,3 +1,6 @@
+def useLegendaryHero(context):
+    return {"attack": 4}
+
 def getItemData():
     return {
         "Pie": {
@@ -22,6 +25,29 @@ def getItemData():
             "heal": 35,
             "attack": 0,
             "defense": 0,
-            "text": "Increased your power!"
+            "text": "Increased your power!",
+            "use": "useLegendaryHero"
+        },
+        "Stick": {
+            "kind": "weapon",
+            "attack": 0,
+            "text": "Its bark is worse than its bite."
+        },
+        "Toy Knife": {
+            "kind": "weapon",
+            "attack": 3,
+            "barStyle": "fast",
+            "damageMultiplier": 1.0,
+            "text": "Made of plastic. A rarity nowadays."
+        },
+        "Bandage": {
+            "kind": "armor",
+            "defense": 0,
+            "text": "It has already been used several times."
+        },
+        "Faded Ribbon": {
+            "kind": "armor",
+            "defense": 3,
+            "text": "If you're cuter, monsters won't hit you as hard."
         }
     }
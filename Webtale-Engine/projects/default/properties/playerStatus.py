@@ -2,6 +2,8 @@ def getPlayerStatus():
     return {
         "name": "CHARA",
         "lv": 1,
+        "exp": 0,
+        "gold": 0,
         "maxHp": 20.0,
         "hp": 20.0,
         "speed": 150.0,
@@ -15,8 +17,8 @@ def getPlayerStatus():
             "SnowPiece",
             "L. Hero",
             "L. Hero",
-            "L. Hero",
-            "L. Hero"
+            "Toy Knife",
+            "Faded Ribbon"
         ],
-        "equippedItems": []
+        "equippedItems": ["Stick", "Bandage"]
     }
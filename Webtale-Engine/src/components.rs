@@ -15,6 +15,120 @@ pub struct MainCamera;
 #[derive(Component)]
 pub struct Soul;
 
+// ソウルモード
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SoulMode {
+    #[default]
+    Red,
+    Blue,
+}
+
+impl SoulMode {
+    // 名前から変換
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "red" => Some(Self::Red),
+            "blue" => Some(Self::Blue),
+            _ => None,
+        }
+    }
+
+    // スクリプト用の名前
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Red => "red",
+            Self::Blue => "blue",
+        }
+    }
+}
+
+// 重力方向
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GravityDirection {
+    #[default]
+    Down,
+    Up,
+    Left,
+    Right,
+}
+
+impl GravityDirection {
+    // 名前から変換
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "down" => Some(Self::Down),
+            "up" => Some(Self::Up),
+            "left" => Some(Self::Left),
+            "right" => Some(Self::Right),
+            _ => None,
+        }
+    }
+
+    // スクリプト用の名前
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Down => "down",
+            Self::Up => "up",
+            Self::Left => "left",
+            Self::Right => "right",
+        }
+    }
+
+    // ボックス内の落下方向
+    pub fn vector(self) -> Vec2 {
+        match self {
+            Self::Down => Vec2::NEG_Y,
+            Self::Up => Vec2::Y,
+            Self::Left => Vec2::NEG_X,
+            Self::Right => Vec2::X,
+        }
+    }
+}
+
+// ソウル移動状態
+#[derive(Component, Default)]
+pub struct SoulMotion {
+    pub moving: bool,
+    pub mode: SoulMode,
+    pub gravity: GravityDirection,
+    pub fall_speed: f32,
+    pub grounded: bool,
+    pub slamming: bool,
+    pub platform: Option<usize>,
+    pub platform_revision: u32,
+    pub box_origin: Vec2,
+    pub sprite_mode: Option<SoulMode>,
+}
+
+impl SoulMotion {
+    // モード切替
+    pub fn set_mode(&mut self, mode: SoulMode, gravity: GravityDirection) {
+        self.mode = mode;
+        self.set_gravity(gravity);
+    }
+
+    // 重力方向変更
+    pub fn set_gravity(&mut self, gravity: GravityDirection) {
+        self.gravity = gravity;
+        self.fall_speed = 0.0;
+        self.grounded = false;
+        self.slamming = false;
+        self.platform = None;
+    }
+}
+
+// 足場表示
+#[derive(Component)]
+pub struct PlatformVisual {
+    pub index: usize,
+}
+
+// 逃走演出
+#[derive(Component)]
+pub struct FleeingSoul {
+    pub timer: Timer,
+}
+
 // ボタン表示
 #[derive(Component)]
 pub struct ButtonVisual {
@@ -40,25 +154,78 @@ pub struct Typewriter {
 }
 
 // 敵表示タグ
-#[derive(Component)] pub struct EnemyBody; 
+#[derive(Component)] pub struct EnemyBody { pub index: usize }
 #[derive(Component)] pub struct EnemyHead { pub base_y: f32, pub timer: f32, pub sway_speed: f32, pub sway_amplitude: f32 }
-#[derive(Component)] pub struct ActCommands { pub commands: Vec<String> }
 #[derive(Component)] pub struct MenuTextItem { pub layer: i32, pub index: i32 }
 #[derive(Component)] pub struct MainDialogText;
 // 演出タグ
 #[derive(Component)] pub struct Vaporizing { pub scan_line: f32, pub image_handle: Handle<Image>, pub initial_y: f32 }
+#[derive(Component)] pub struct SpareFade { pub timer: Timer }
 #[derive(Component)] pub struct DustParticle { pub velocity: Vec3, pub timer: Timer, pub max_alpha: f32 }
 #[derive(Component)] pub struct SpeechBubble;
 #[derive(Component)] pub struct AttackTargetBox;
 #[derive(Component)] pub struct AttackBar { pub speed: f32, pub moving: bool, pub flash_timer: Timer, pub flash_state: bool }
 #[derive(Component)] pub struct SliceEffect { pub timer: Timer, pub frame_index: usize }
-#[derive(Component)] pub struct PendingDamage { pub timer: Timer, pub damage: i32, pub target_pos: Vec3 }
+#[derive(Component)] pub struct PendingItemUse { pub item_name: String }
+#[derive(Component)] pub struct PendingDamage { pub timer: Timer, pub damage: i32, pub target: usize, pub target_pos: Vec3 }
 #[derive(Component)] pub struct DamageNumber { pub timer: Timer, pub velocity_y: f32, pub gravity: f32, pub start_y: f32 }
 #[derive(Component)] pub struct EnemyHpBar { pub lifespan: Timer, pub animation: Timer, pub start_width: f32, pub target_width: f32 }
 #[derive(Component)] pub struct EnemyHpBarForeground;
 #[derive(Component)] pub struct BorderVisual;
 #[derive(Component)] pub struct BackgroundVisual;
 
+// 弾の色
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BulletColor {
+    #[default]
+    White,
+    Blue,
+    Orange,
+    Green,
+}
+
+impl BulletColor {
+    // 名前から変換
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "white" => Some(Self::White),
+            "blue" | "cyan" => Some(Self::Blue),
+            "orange" => Some(Self::Orange),
+            "green" => Some(Self::Green),
+            _ => None,
+        }
+    }
+
+    // スプライト色
+    pub fn tint(self) -> Color {
+        match self {
+            Self::White => Color::WHITE,
+            Self::Blue => Color::srgb(0.08, 0.66, 1.0),
+            Self::Orange => Color::srgb(0.99, 0.65, 0.0),
+            Self::Green => Color::srgb(0.0, 0.75, 0.0),
+        }
+    }
+
+    // 青は移動中、橙は静止中のみ被弾
+    pub fn hurts(self, moving: bool) -> bool {
+        match self {
+            Self::White | Self::Green => true,
+            Self::Blue => moving,
+            Self::Orange => !moving,
+        }
+    }
+}
+
+// 弾共通データ
+#[derive(Component, Clone, Debug)]
+pub struct Bullet {
+    pub damage: i32,
+    pub color: BulletColor,
+    pub destroy_on_hit: bool,
+    pub on_collide: Option<PyObjectRef>,
+    pub touching: bool,
+}
+
 // 弾幕データ
 #[derive(Component)]
 pub struct LeapFrogBullet {
@@ -66,7 +233,6 @@ pub struct LeapFrogBullet {
     pub timer: Timer,
     pub velocity: Vec3,
     pub gravity: Vec3,
-    pub damage: i32,
     pub jump_speed: f32,
     pub jump_angle: f32,
     pub jump_texture: String,
@@ -79,10 +245,32 @@ pub struct ExpressionBullet {
     pub update_exprs: Vec<ExprAssignment>,
     pub delete_expr: Option<evalexpr::Node>,
     pub texture_expr: Option<evalexpr::Node>,
-    pub damage: i32,
     pub last_texture: Option<String>,
 }
 
+// 弾の当たり判定形状
+#[derive(Clone, Debug)]
+pub enum HitboxShape {
+    Circle { radius: f32 },
+    Rect { width: f32, height: f32 },
+    Capsule { length: f32, radius: f32 },
+    Mask { threshold: f32 },
+}
+
+// 弾の当たり判定
+#[derive(Component, Clone, Debug)]
+pub struct Hitbox {
+    pub shape: HitboxShape,
+    pub angle: f32,
+    pub offset: Vec2,
+}
+
+impl Default for Hitbox {
+    fn default() -> Self {
+        Self { shape: HitboxShape::Circle { radius: 10.0 }, angle: 0.0, offset: Vec2::ZERO }
+    }
+}
+
 pub enum LeapFrogState {
     Waiting,
     Jumping,
@@ -132,9 +320,7 @@ pub struct EditorWindow;
 // Python弾幕
 #[derive(Component)]
 pub struct PythonBullet {
-    pub script_name: String,
     pub bullet_data: PyObjectRef,
-    pub damage: i32,
 }
 
 // プレビュー表示
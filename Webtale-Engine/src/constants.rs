@@ -1,8 +1,13 @@
 use bevy::prelude::Color;
 use bevy::prelude::*;
 
-// プロジェクト設定
-pub const PROJECT_NAME: &str = "default";
+// エンジンバージョン
+pub const ENGINE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// 既定プロジェクト
+pub const DEFAULT_PROJECT_NAME: &str = "default";
+// プロジェクト指定環境変数
+pub const PROJECT_ENV_VAR: &str = "WEBTALE_PROJECT";
 
 // 画面サイズ
 pub const WINDOW_WIDTH: f32 = 640.0;
@@ -31,6 +36,7 @@ pub const Z_ENEMY_HEAD: f32 = 4.0;
 
 pub const Z_BORDER: f32 = 5.0;
 pub const Z_BG: f32 = 6.0;
+pub const Z_PLATFORM: f32 = 7.0;
 
 pub const Z_ATTACK_TARGET: f32 = 10.0;
 pub const Z_ATTACK_BAR: f32 = 11.0;
@@ -64,6 +70,24 @@ pub const MENU_LAYER_ACT_COMMAND: i32 = 10;
 // ページ設定
 pub const ITEMS_PER_PAGE: usize = 4;
 
+// 最大敵数
+pub const MAX_ENEMIES: usize = 3;
+
+// 攻撃ターン既定値
+pub const ATTACK_BOX: [f32; 4] = [217.0, 125.0, 417.0, 385.0];
+pub const ATTACK_DURATION: f32 = 5.0;
+// 魂の当たり判定半径
+pub const SOUL_HITBOX_RADIUS: f32 = 6.0;
+
+// 青ソウル挙動
+pub const SOUL_GRAVITY: f32 = 540.0;
+pub const SOUL_JUMP_SPEED: f32 = 180.0;
+pub const SOUL_JUMP_CUT_SPEED: f32 = 30.0;
+pub const SOUL_MAX_FALL_SPEED: f32 = 450.0;
+pub const SOUL_SLAM_SPEED: f32 = 900.0;
+// 足場の厚さ
+pub const PLATFORM_THICKNESS: f32 = 4.0;
+
 // 座標変換
 pub fn gml_to_bevy(x: f32, y: f32) -> Vec3 {
     Vec3::new(ORIGIN_X + x, ORIGIN_Y - y, 0.0)
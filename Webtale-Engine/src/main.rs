@@ -1,13 +1,18 @@
 use bevy::prelude::*;
 use bevy::asset::{AssetMetaCheck, AssetPlugin};
+use bevy::asset::io::AssetSourceBuilder;
 use bevy_egui::EguiPlugin;
 
 mod constants;
 mod components;
 mod resources;
+mod project;
+mod project_archive;
+mod project_fs;
 mod python_scripts;
 mod python_utils;
 mod systems;
+mod validator;
 
 use constants::*;
 use resources::*;
@@ -15,7 +20,18 @@ use systems::*;
 
 // アプリ起動
 fn main() {
+    // 検証モード
+    if let Some(target) = validator::validate_target_from_args() {
+        std::process::exit(validator::run(&target));
+    }
+
     App::new()
+        // プロジェクトアセット
+        .register_asset_source(
+            project::PROJECTS_ASSET_SOURCE,
+            AssetSourceBuilder::platform_default("projects", None)
+                .with_reader(project_archive::asset_reader("projects")),
+        )
         // プラグイン設定
         .add_plugins(
             DefaultPlugins
@@ -42,16 +58,26 @@ fn main() {
         .add_plugins(EguiPlugin)
         // クリアカラー
         .insert_resource(ClearColor(Color::BLACK))
+        // プロジェクト選択
+        .insert_resource(CurrentProject::from_startup())
         // バトルボックス初期値
         .insert_resource(BattleBox {
             current: Rect::new(32.0, 250.0, 602.0, 385.0),
             target: Rect::new(32.0, 250.0, 602.0, 385.0),
+            ..default()
         })
         // エディタリソース
         .init_resource::<EditorState>()
         .init_resource::<EditorPreviewTexture>()
         .init_resource::<DanmakuPreviewTexture>()
         .init_resource::<DanmakuScripts>()
+        .init_resource::<DanmakuWave>()
+        .add_event::<BattleFinished>()
+        .add_event::<SoulHitEvent>()
+        .add_event::<SoulHealEvent>()
+        .init_resource::<ScreenShake>()
+        .init_resource::<PhaseScripts>()
+        .init_resource::<ScriptHotReload>()
         // メニュー描画キャッシュ
         .init_resource::<MenuRenderCache>()
         // Python実行環境
@@ -68,29 +94,49 @@ fn main() {
         .add_systems(Update, ui::menu_render_system)
         .add_systems(Update, player::soul_position_sync)
         .add_systems(Update, player::soul_combat_movement)
+        .add_systems(Update, player::soul_mode_sprite_update)
         .add_systems(Update, ui::update_box_size)
         .add_systems(Update, ui::draw_battle_box)
+        .add_systems(Update, ui::draw_box_platforms)
         .add_systems(Update, ui::draw_ui_status)
         .add_systems(Update, ui::update_button_sprites)
         .add_systems(Update, ui::animate_text)
         .add_systems(Update, ui::animate_enemy_head)
         .add_systems(Update, editor::editor_ui_system)
+        .add_systems(Update, hot_reload::hot_reload_scripts)
         // 戦闘システム
         .add_systems(Update, (
             combat::battle_flow_control,
             combat::attack_bar_update,
             combat::apply_pending_damage,   
+            combat::item_use_update,
+            combat::heal_over_time_update,
             combat::animate_slice_effect,
             combat::damage_number_update,   
             combat::enemy_hp_bar_update,    
             combat::vaporize_enemy_system, 
             combat::dust_particle_update,
+            combat::spare_fade_update,
+            combat::soul_flee_update,
+            combat::victory_reward_update,
+            combat::battle_end_update,
+            combat::report_battle_result,
         ))
         .add_systems(Update, (
             combat::leapfrog_bullet_update,
             combat::combat_turn_manager,
-            combat::soul_collision_detection,
-            combat::invincibility_update,
+            combat::danmaku_wave_update.before(combat::combat_turn_manager),
+            combat::battle_box_script_update,
+            combat::soul_script_update,
+            // 被弾処理は判定→適用→演出→敗北の順
+            (
+                combat::soul_collision_detection,
+                (combat::soul_hit_damage, combat::soul_hit_invincibility, combat::soul_heal_apply),
+                combat::soul_hit_feedback,
+                combat::soul_defeat_start,
+                combat::invincibility_update,
+            ).chain(),
+            combat::screen_shake_update,
             combat::heart_defeated_update,
             combat::heart_shard_update,
             combat::game_over_sequence_update,
@@ -0,0 +1,255 @@
+// プロジェクトマニフェスト読み込み
+use bevy::asset::Asset;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use rustpython_vm::builtins::PyDictRef;
+use rustpython_vm::compiler::Mode;
+use crate::constants::ENGINE_VERSION;
+use crate::python_scripts;
+use crate::python_utils::{read_option_f32, read_option_string, read_option_vec_string};
+use crate::resources::{CurrentProject, ProjectManifest, PythonRuntime};
+
+// プロジェクトアセットソース名
+pub const PROJECTS_ASSET_SOURCE: &str = "projects";
+// プロジェクトアセット接頭辞
+const PROJECT_ASSET_PREFIX: &str = "project://";
+
+// アセットパス解決
+pub fn resolve_asset_path(project_name: &str, manifest: &ProjectManifest, path: &str) -> String {
+    if let Some(relative) = path.strip_prefix(PROJECT_ASSET_PREFIX) {
+        let root = manifest
+            .asset_roots
+            .iter()
+            .find(|root| python_scripts::project_file_exists(project_name, &format!("{}/{}", root, relative)))
+            .or(manifest.asset_roots.first());
+        return match root {
+            Some(root) => format!("{}://{}/{}/{}", PROJECTS_ASSET_SOURCE, project_name, root, relative),
+            None => {
+                println!("Warning: project assetRoots empty {}", path);
+                relative.to_string()
+            }
+        };
+    }
+
+    if path.contains("://") {
+        return path.to_string();
+    }
+
+    // プロジェクト優先
+    for root in &manifest.asset_roots {
+        let relative = format!("{}/{}", root, path);
+        if python_scripts::project_file_exists(project_name, &relative) {
+            return format!("{}://{}/{}", PROJECTS_ASSET_SOURCE, project_name, relative);
+        }
+    }
+    path.to_string()
+}
+
+// プロジェクトアセット読み込み
+#[derive(SystemParam)]
+pub struct ProjectAssets<'w> {
+    asset_server: Res<'w, AssetServer>,
+    current_project: Res<'w, CurrentProject>,
+    manifest: Res<'w, ProjectManifest>,
+}
+
+impl ProjectAssets<'_> {
+    pub fn load<A: Asset>(&self, path: &str) -> Handle<A> {
+        self.asset_server.load(resolve_asset_path(&self.current_project.name, &self.manifest, path))
+    }
+}
+
+// バージョン分解
+fn parse_version(text: &str) -> Vec<u32> {
+    text.trim()
+        .trim_start_matches('v')
+        .split('.')
+        .map(|part| part.trim().parse().unwrap_or(0))
+        .collect()
+}
+
+// エンジン対応判定
+pub fn engine_supports(required: &str) -> bool {
+    if required.trim().is_empty() {
+        return true;
+    }
+    let required = parse_version(required);
+    let current = parse_version(ENGINE_VERSION);
+    for i in 0..required.len().max(current.len()) {
+        let required_part = required.get(i).copied().unwrap_or(0);
+        let current_part = current.get(i).copied().unwrap_or(0);
+        if current_part != required_part {
+            return current_part > required_part;
+        }
+    }
+    true
+}
+
+// マニフェスト読み込み
+pub fn load_project_manifest(python_runtime: &PythonRuntime, project_name: &str) -> Result<ProjectManifest, String> {
+    let mut manifest = ProjectManifest::default_layout(project_name);
+    let script = match python_scripts::get_manifest_script(project_name) {
+        Some(script) => script,
+        None => return Ok(manifest),
+    };
+
+    let mut error: Option<String> = None;
+    python_runtime.interpreter.enter(|vm| {
+        let scope = vm.new_scope_with_builtins();
+        let code_obj = match vm.compile(&script, Mode::Exec, "project.py".to_string()) {
+            Ok(code_obj) => code_obj,
+            Err(err) => {
+                error = Some(format!("python compile project.py {:?}", err));
+                return;
+            }
+        };
+        if let Err(err) = vm.run_code_obj(code_obj, scope.clone()) {
+            vm.print_exception(err.clone());
+            error = Some("project.py failed".to_string());
+            return;
+        }
+        let func = match scope.globals.get_item_opt("getProject", vm) {
+            Ok(Some(func)) => func,
+            Ok(None) => {
+                error = Some("project.py missing getProject".to_string());
+                return;
+            }
+            Err(err) => {
+                vm.print_exception(err.clone());
+                error = Some("project.py lookup getProject failed".to_string());
+                return;
+            }
+        };
+        let dict = match func.call((), vm).and_then(|result| result.try_into_value::<PyDictRef>(vm)) {
+            Ok(dict) => dict,
+            Err(err) => {
+                vm.print_exception(err.clone());
+                error = Some("project.py getProject failed".to_string());
+                return;
+            }
+        };
+
+        if let Some(name) = read_option_string(vm, &dict, "name", "project", true) {
+            manifest.name = name;
+        }
+        if let Some(author) = read_option_string(vm, &dict, "author", "project", false) {
+            manifest.author = author;
+        }
+        if let Some(engine_version) = read_option_string(vm, &dict, "engineVersion", "project", false) {
+            manifest.engine_version = engine_version;
+        }
+        if let Some(asset_roots) = read_option_vec_string(vm, &dict, "assetRoots", "project", false) {
+            manifest.asset_roots = asset_roots;
+        }
+        if let Some(music) = read_option_string(vm, &dict, "music", "project", false) {
+            if !music.is_empty() {
+                manifest.music = Some(music);
+            }
+        }
+        if let Some(hurt_sound) = read_option_string(vm, &dict, "hurtSound", "project", false) {
+            if !hurt_sound.is_empty() {
+                manifest.hurt_sound = Some(hurt_sound);
+            }
+        }
+
+        match dict.get_item_opt("flee", vm) {
+            Ok(Some(value)) => match value.try_into_value::<PyDictRef>(vm) {
+                Ok(flee) => {
+                    if let Some(chance) = read_option_f32(vm, &flee, "chance", "project flee", false) {
+                        manifest.flee_chance = chance;
+                    }
+                    if let Some(chance) = read_option_f32(vm, &flee, "chancePerTurn", "project flee", false) {
+                        manifest.flee_chance_per_turn = chance;
+                    }
+                }
+                Err(err) => {
+                    vm.print_exception(err.clone());
+                    println!("Warning: project flee invalid");
+                }
+            },
+            Ok(None) => {}
+            Err(err) => vm.print_exception(err),
+        }
+
+        match dict.get_item_opt("damage", vm) {
+            Ok(Some(value)) => match value.try_into_value::<PyDictRef>(vm) {
+                Ok(damage) => {
+                    manifest.damage_script = read_option_string(vm, &damage, "script", "project damage", false);
+                    manifest.damage_formula = read_option_string(vm, &damage, "formula", "project damage", false);
+                }
+                Err(err) => {
+                    vm.print_exception(err.clone());
+                    println!("Warning: project damage invalid");
+                }
+            },
+            Ok(None) => {}
+            Err(err) => vm.print_exception(err),
+        }
+
+        match dict.get_item_opt("entry", vm) {
+            Ok(Some(value)) => match value.try_into_value::<PyDictRef>(vm) {
+                Ok(entry) => {
+                    if let Some(path) = read_option_string(vm, &entry, "player", "project entry", false) {
+                        manifest.player_script = path;
+                    }
+                    if let Some(path) = read_option_string(vm, &entry, "enemy", "project entry", false) {
+                        manifest.enemy_script = path;
+                    }
+                    if let Some(paths) = read_option_vec_string(vm, &entry, "encounter", "project entry", false) {
+                        manifest.encounter = paths;
+                    }
+                    if let Some(path) = read_option_string(vm, &entry, "item", "project entry", false) {
+                        manifest.item_script = path;
+                    }
+                    if let Some(phase) = read_option_string(vm, &entry, "initialPhase", "project entry", false) {
+                        manifest.initial_phase = phase;
+                    }
+                }
+                Err(err) => {
+                    vm.print_exception(err.clone());
+                    println!("Warning: project entry invalid");
+                }
+            },
+            Ok(None) => {}
+            Err(err) => vm.print_exception(err),
+        }
+
+        match dict.get_item_opt("fonts", vm) {
+            Ok(Some(value)) => match value.try_into_value::<PyDictRef>(vm) {
+                Ok(fonts) => {
+                    if let Some(path) = read_option_string(vm, &fonts, "main", "project fonts", false) {
+                        manifest.font_main = path;
+                    }
+                    if let Some(path) = read_option_string(vm, &fonts, "dialog", "project fonts", false) {
+                        manifest.font_dialog = path;
+                    }
+                    if let Some(path) = read_option_string(vm, &fonts, "hpLabel", "project fonts", false) {
+                        manifest.font_hp_label = path;
+                    }
+                    if let Some(path) = read_option_string(vm, &fonts, "damage", "project fonts", false) {
+                        manifest.font_damage = path;
+                    }
+                }
+                Err(err) => {
+                    vm.print_exception(err.clone());
+                    println!("Warning: project fonts invalid");
+                }
+            },
+            Ok(None) => {}
+            Err(err) => vm.print_exception(err),
+        }
+    });
+
+    if let Some(error) = error {
+        return Err(format!("projects/{}/{}", project_name, error));
+    }
+
+    if !engine_supports(&manifest.engine_version) {
+        return Err(format!(
+            "project {} requires engine {} or newer (current {})",
+            manifest.name, manifest.engine_version, ENGINE_VERSION
+        ));
+    }
+
+    Ok(manifest)
+}
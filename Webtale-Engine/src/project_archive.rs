@@ -0,0 +1,225 @@
+// プロジェクトアーカイブ
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use std::path::Path;
+use bevy::asset::io::{AssetReader, AssetReaderError, AssetSource, ErasedAssetReader, PathStream, Reader, VecReader};
+use rustpython_vm::builtins::PyDictRef;
+use rustpython_vm::compiler::Mode;
+use crate::project;
+use crate::project_fs;
+use crate::python_utils::{read_option_string, read_option_vec_string};
+use crate::resources::PythonRuntime;
+
+// アーカイブ拡張子
+pub const ARCHIVE_EXTENSION: &str = "wtproj";
+// マニフェストファイル
+const ARCHIVE_MANIFEST: &str = "project.py";
+
+// アーカイブ指定判定
+pub fn is_archive_path(path: &str) -> bool {
+    Path::new(path).extension().and_then(|ext| ext.to_str()) == Some(ARCHIVE_EXTENSION)
+}
+
+// アーカイブ展開
+pub fn mount_archive_bytes(project_name: &str, bytes: Vec<u8>) -> Result<String, String> {
+    if !project_fs::is_safe_relative_path(project_name) || project_name.contains('/') {
+        return Err(format!("archive name invalid {}", project_name));
+    }
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).map_err(|err| format!("archive open {}", err))?;
+    let mut files = HashMap::new();
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(|err| format!("archive entry {} {}", index, err))?;
+        if entry.is_dir() {
+            continue;
+        }
+        let name = entry.name().replace('\\', "/");
+        if !project_fs::is_safe_relative_path(&name) {
+            return Err(format!("archive path invalid {}", name));
+        }
+        let mut contents = Vec::new();
+        entry.read_to_end(&mut contents).map_err(|err| format!("archive read {} {}", name, err))?;
+        files.insert(name, contents);
+    }
+    if !files.contains_key(ARCHIVE_MANIFEST) {
+        return Err(format!("archive missing {}", ARCHIVE_MANIFEST));
+    }
+    project_fs::mount(project_name, files);
+    Ok(project_name.to_string())
+}
+
+// アーカイブファイル読み込み
+pub fn mount_archive_file(path: &str) -> Result<String, String> {
+    let project_name = Path::new(path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .ok_or_else(|| format!("archive name invalid {}", path))?;
+    let bytes = std::fs::read(path).map_err(|err| format!("archive read {} {}", path, err))?;
+    mount_archive_bytes(project_name, bytes)
+}
+
+// アーカイブ書き出し
+pub fn export_project(project_name: &str, output_path: &Path) -> Result<usize, String> {
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    let files = project_fs::list_all_files(project_name);
+    if files.is_empty() {
+        return Err(format!("project empty {}", project_name));
+    }
+    if let Some(parent) = output_path.parent() {
+        std::fs::create_dir_all(parent).map_err(|err| format!("export dir {} {}", parent.display(), err))?;
+    }
+    let file = std::fs::File::create(output_path).map_err(|err| format!("export create {} {}", output_path.display(), err))?;
+    let mut writer = zip::ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    for relative_path in &files {
+        let contents = match project_fs::read_bytes(project_name, relative_path) {
+            Some(contents) => contents,
+            None => return Err(format!("export read {}", relative_path)),
+        };
+        writer
+            .start_file(relative_path.as_str(), options)
+            .and_then(|_| writer.write_all(&contents).map_err(zip::result::ZipError::from))
+            .map_err(|err| format!("export write {} {}", relative_path, err))?;
+    }
+    writer.finish().map_err(|err| format!("export finish {}", err))?;
+    Ok(files.len())
+}
+
+// 書き出し先パス
+pub fn default_export_path(project_name: &str) -> std::path::PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("exports")
+        .join(format!("{}.{}", project_name, ARCHIVE_EXTENSION))
+}
+
+// 完全性検査
+pub fn check_project_complete(python_runtime: &PythonRuntime, project_name: &str) -> Vec<String> {
+    let mut missing = Vec::new();
+    if !project_fs::file_exists(project_name, ARCHIVE_MANIFEST) {
+        missing.push(ARCHIVE_MANIFEST.to_string());
+    }
+    let manifest = match project::load_project_manifest(python_runtime, project_name) {
+        Ok(manifest) => manifest,
+        Err(message) => {
+            missing.push(message);
+            return missing;
+        }
+    };
+
+    let mut required = vec![
+        manifest.player_script.clone(),
+        manifest.item_script.clone(),
+        "phases/phase_api.py".to_string(),
+        "danmaku/api.py".to_string(),
+    ];
+    required.extend(manifest.enemy_scripts());
+    if let Some(damage_script) = &manifest.damage_script {
+        required.push(damage_script.clone());
+    }
+    if !manifest.initial_phase.is_empty() {
+        required.push(format!("phases/{}.py", manifest.initial_phase));
+    }
+
+    // 敵ステータス参照
+    for enemy_script in manifest.enemy_scripts() {
+        let Some(script) = project_fs::read_to_string(project_name, &enemy_script) else { continue };
+        python_runtime.interpreter.enter(|vm| {
+            let scope = vm.new_scope_with_builtins();
+            let code_obj = match vm.compile(&script, Mode::Exec, enemy_script.clone()) {
+                Ok(code_obj) => code_obj,
+                Err(err) => {
+                    missing.push(format!("{} compile {:?}", enemy_script, err));
+                    return;
+                }
+            };
+            if let Err(err) = vm.run_code_obj(code_obj, scope.clone()) {
+                vm.print_exception(err.clone());
+                missing.push(format!("{} failed", enemy_script));
+                return;
+            }
+            let result = match scope.globals.get_item_opt("getEnemyStatus", vm) {
+                Ok(Some(func)) => match func.call((), vm) {
+                    Ok(result) => result,
+                    Err(err) => {
+                        vm.print_exception(err.clone());
+                        missing.push(format!("{} getEnemyStatus failed", enemy_script));
+                        return;
+                    }
+                },
+                _ => {
+                    missing.push(format!("{} missing getEnemyStatus", enemy_script));
+                    return;
+                }
+            };
+            let dicts = match result.clone().try_into_value::<PyDictRef>(vm) {
+                Ok(dict) => vec![dict],
+                Err(_) => match result.try_into_value::<Vec<PyDictRef>>(vm) {
+                    Ok(dicts) => dicts,
+                    Err(err) => {
+                        vm.print_exception(err.clone());
+                        missing.push(format!("{} getEnemyStatus failed", enemy_script));
+                        return;
+                    }
+                },
+            };
+            for dict in dicts {
+                if let Some(attacks) = read_option_vec_string(vm, &dict, "attackPatterns", "enemyStatus", false) {
+                    required.extend(attacks.into_iter().map(|name| format!("danmaku/{}.py", name)));
+                }
+                if let Some(phase_script) = read_option_string(vm, &dict, "phaseScript", "enemyStatus", false) {
+                    if !phase_script.is_empty() {
+                        required.push(format!("phases/{}.py", phase_script));
+                    }
+                }
+            }
+        });
+    }
+
+    for relative_path in required {
+        if !project_fs::file_exists(project_name, &relative_path) && !missing.contains(&relative_path) {
+            missing.push(relative_path);
+        }
+    }
+    missing
+}
+
+// アーカイブ対応アセットリーダー
+struct ProjectAssetReader {
+    fallback: Box<dyn ErasedAssetReader>,
+}
+
+impl ProjectAssetReader {
+    fn read_archive(path: &Path) -> Option<Vec<u8>> {
+        let path = path.to_str()?.replace('\\', "/");
+        let (project_name, relative_path) = path.split_once('/')?;
+        project_fs::read_mounted(project_name, relative_path)
+    }
+}
+
+impl AssetReader for ProjectAssetReader {
+    async fn read<'a>(&'a self, path: &'a Path) -> Result<Box<dyn Reader + 'a>, AssetReaderError> {
+        if let Some(bytes) = Self::read_archive(path) {
+            return Ok(Box::new(VecReader::new(bytes)));
+        }
+        self.fallback.read(path).await
+    }
+
+    async fn read_meta<'a>(&'a self, path: &'a Path) -> Result<Box<dyn Reader + 'a>, AssetReaderError> {
+        self.fallback.read_meta(path).await
+    }
+
+    async fn read_directory<'a>(&'a self, path: &'a Path) -> Result<Box<PathStream>, AssetReaderError> {
+        self.fallback.read_directory(path).await
+    }
+
+    async fn is_directory<'a>(&'a self, path: &'a Path) -> Result<bool, AssetReaderError> {
+        self.fallback.is_directory(path).await
+    }
+}
+
+// アセットリーダー生成
+pub fn asset_reader(path: &str) -> impl FnMut() -> Box<dyn ErasedAssetReader> + Send + Sync {
+    let mut fallback = AssetSource::get_default_reader(path.to_string());
+    move || Box::new(ProjectAssetReader { fallback: fallback() })
+}
@@ -0,0 +1,260 @@
+// プロジェクト仮想ファイルシステム
+use std::collections::HashMap;
+use std::path::{Component, Path};
+use std::sync::{OnceLock, RwLock};
+use std::time::SystemTime;
+
+// マウント済みアーカイブ
+type MountedFiles = HashMap<String, Vec<u8>>;
+
+fn mounted() -> &'static RwLock<HashMap<String, MountedFiles>> {
+    static MOUNTED: OnceLock<RwLock<HashMap<String, MountedFiles>>> = OnceLock::new();
+    MOUNTED.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+// アーカイブマウント
+pub fn mount(project: &str, files: MountedFiles) {
+    if let Ok(mut projects) = mounted().write() {
+        projects.insert(project.to_string(), files);
+    }
+}
+
+// マウント確認
+pub fn is_mounted(project: &str) -> bool {
+    mounted().read().map(|projects| projects.contains_key(project)).unwrap_or(false)
+}
+
+// マウント済みファイル読み込み
+pub fn read_mounted(project: &str, relative_path: &str) -> Option<Vec<u8>> {
+    let projects = mounted().read().ok()?;
+    projects.get(project)?.get(relative_path).cloned()
+}
+
+// 相対パス検証
+pub fn is_safe_relative_path(relative_path: &str) -> bool {
+    let path = Path::new(relative_path);
+    !relative_path.is_empty()
+        && path.components().all(|component| matches!(component, Component::Normal(_)))
+}
+
+// パス結合
+fn join_path(project: &str, relative_path: &str) -> String {
+    if relative_path.is_empty() {
+        project.to_string()
+    } else {
+        format!("{}/{}", project, relative_path)
+    }
+}
+
+// バイナリ読み込み
+pub fn read_bytes(project: &str, relative_path: &str) -> Option<Vec<u8>> {
+    if !is_safe_relative_path(project) || !is_safe_relative_path(relative_path) {
+        return None;
+    }
+    if is_mounted(project) {
+        return read_mounted(project, relative_path);
+    }
+    backend::read_bytes(&join_path(project, relative_path))
+}
+
+// ファイル読み込み
+pub fn read_to_string(project: &str, relative_path: &str) -> Option<String> {
+    read_bytes(project, relative_path).and_then(|bytes| String::from_utf8(bytes).ok())
+}
+
+// ファイル存在確認
+pub fn file_exists(project: &str, relative_path: &str) -> bool {
+    if !is_safe_relative_path(project) || !is_safe_relative_path(relative_path) {
+        return false;
+    }
+    if is_mounted(project) {
+        return read_mounted(project, relative_path).is_some();
+    }
+    backend::is_file(&join_path(project, relative_path))
+}
+
+// 更新時刻取得
+pub fn modified_time(project: &str, relative_path: &str) -> Option<SystemTime> {
+    if !is_safe_relative_path(project) || !is_safe_relative_path(relative_path) || is_mounted(project) {
+        return None;
+    }
+    backend::modified_time(&join_path(project, relative_path))
+}
+
+// プロジェクト存在確認
+pub fn project_exists(project: &str) -> bool {
+    is_safe_relative_path(project) && (is_mounted(project) || backend::is_dir(project))
+}
+
+// プロジェクト一覧
+pub fn list_projects() -> Vec<String> {
+    let mut names = backend::list_dir("", true);
+    if let Ok(projects) = mounted().read() {
+        names.extend(projects.keys().cloned());
+    }
+    names.sort();
+    names.dedup();
+    names
+}
+
+// プロジェクト内全ファイル
+pub fn list_all_files(project: &str) -> Vec<String> {
+    if !is_safe_relative_path(project) {
+        return vec![];
+    }
+    let mut files = if is_mounted(project) {
+        mounted()
+            .read()
+            .ok()
+            .and_then(|projects| projects.get(project).map(|files| files.keys().cloned().collect()))
+            .unwrap_or_default()
+    } else {
+        backend::walk(project)
+    };
+    files.sort();
+    files
+}
+
+// ディレクトリ内ファイル一覧
+pub fn list_files(project: &str, relative_dir: &str) -> Vec<String> {
+    if !is_safe_relative_path(project) || (!relative_dir.is_empty() && !is_safe_relative_path(relative_dir)) {
+        return vec![];
+    }
+    let mut names = if is_mounted(project) {
+        let prefix = if relative_dir.is_empty() { String::new() } else { format!("{}/", relative_dir) };
+        list_all_files(project)
+            .into_iter()
+            .filter_map(|path| path.strip_prefix(&prefix).map(|name| name.to_string()))
+            .filter(|name| !name.contains('/'))
+            .collect()
+    } else {
+        backend::list_dir(&join_path(project, relative_dir), false)
+    };
+    names.sort();
+    names
+}
+
+// ディスク実装
+#[cfg(not(any(target_arch = "wasm32", feature = "embedded_projects")))]
+mod backend {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    fn resolve(path: &str) -> PathBuf {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("projects");
+        if path.is_empty() {
+            root
+        } else {
+            root.join(path)
+        }
+    }
+
+    pub fn read_bytes(path: &str) -> Option<Vec<u8>> {
+        fs::read(resolve(path)).ok()
+    }
+
+    pub fn is_file(path: &str) -> bool {
+        resolve(path).is_file()
+    }
+
+    pub fn modified_time(path: &str) -> Option<std::time::SystemTime> {
+        fs::metadata(resolve(path)).and_then(|metadata| metadata.modified()).ok()
+    }
+
+    pub fn is_dir(path: &str) -> bool {
+        resolve(path).is_dir()
+    }
+
+    pub fn list_dir(path: &str, dirs: bool) -> Vec<String> {
+        let entries = match fs::read_dir(resolve(path)) {
+            Ok(entries) => entries,
+            Err(_) => return vec![],
+        };
+        entries
+            .flatten()
+            .filter(|entry| entry.path().is_dir() == dirs)
+            .filter_map(|entry| entry.file_name().to_str().map(|name| name.to_string()))
+            .collect()
+    }
+
+    pub fn walk(path: &str) -> Vec<String> {
+        let mut files = Vec::new();
+        let mut pending = vec![String::new()];
+        while let Some(relative_dir) = pending.pop() {
+            let dir_path = if relative_dir.is_empty() { path.to_string() } else { format!("{}/{}", path, relative_dir) };
+            for name in list_dir(&dir_path, true) {
+                pending.push(if relative_dir.is_empty() { name } else { format!("{}/{}", relative_dir, name) });
+            }
+            for name in list_dir(&dir_path, false) {
+                files.push(if relative_dir.is_empty() { name } else { format!("{}/{}", relative_dir, name) });
+            }
+        }
+        files
+    }
+}
+
+// 埋め込み実装
+#[cfg(any(target_arch = "wasm32", feature = "embedded_projects"))]
+mod backend {
+    use include_dir::{include_dir, Dir};
+
+    static PROJECTS: Dir<'static> = include_dir!("$CARGO_MANIFEST_DIR/projects");
+
+    fn get_dir(path: &str) -> Option<&'static Dir<'static>> {
+        if path.is_empty() {
+            Some(&PROJECTS)
+        } else {
+            PROJECTS.get_dir(path)
+        }
+    }
+
+    pub fn read_bytes(path: &str) -> Option<Vec<u8>> {
+        PROJECTS.get_file(path).map(|file| file.contents().to_vec())
+    }
+
+    pub fn is_file(path: &str) -> bool {
+        PROJECTS.get_file(path).is_some()
+    }
+
+    pub fn modified_time(_path: &str) -> Option<std::time::SystemTime> {
+        None
+    }
+
+    pub fn is_dir(path: &str) -> bool {
+        get_dir(path).is_some()
+    }
+
+    pub fn list_dir(path: &str, dirs: bool) -> Vec<String> {
+        let dir = match get_dir(path) {
+            Some(dir) => dir,
+            None => return vec![],
+        };
+        let paths: Vec<&std::path::Path> = if dirs {
+            dir.dirs().map(|entry| entry.path()).collect()
+        } else {
+            dir.files().map(|entry| entry.path()).collect()
+        };
+        paths
+            .into_iter()
+            .filter_map(|path| path.file_name().and_then(|name| name.to_str()).map(|name| name.to_string()))
+            .collect()
+    }
+
+    pub fn walk(path: &str) -> Vec<String> {
+        let dir = match get_dir(path) {
+            Some(dir) => dir,
+            None => return vec![],
+        };
+        let mut files = Vec::new();
+        let mut pending = vec![dir];
+        while let Some(dir) = pending.pop() {
+            pending.extend(dir.dirs());
+            for file in dir.files() {
+                if let Some(relative) = file.path().strip_prefix(path).ok().and_then(|relative| relative.to_str()) {
+                    files.push(relative.replace('\\', "/"));
+                }
+            }
+        }
+        files
+    }
+}
@@ -1,32 +1,38 @@
 // Pythonスクリプト読み込み
-use std::fs;
-use std::path::{Path, PathBuf};
+use crate::project_fs;
 
-// プロジェクトルートパス
-fn project_root(project: &str) -> PathBuf {
-    Path::new(env!("CARGO_MANIFEST_DIR"))
-        .join("projects")
-        .join(project)
+// プロジェクト存在確認
+pub fn project_exists(project: &str) -> bool {
+    project_fs::project_exists(project)
+}
+
+// プロジェクト一覧
+pub fn list_project_names() -> Vec<String> {
+    project_fs::list_projects()
 }
 
 // スクリプト読み込み
-fn read_script(path: PathBuf) -> Option<String> {
-    fs::read_to_string(path).ok()
+fn read_script(project: &str, relative_path: &str) -> Option<String> {
+    project_fs::read_to_string(project, relative_path)
 }
 
-// アイテムスクリプト
-pub fn get_item_script(project: &str) -> Option<String> {
-    read_script(project_root(project).join("properties").join("item.py"))
+// プロジェクト内ファイル存在確認
+pub fn project_file_exists(project: &str, relative_path: &str) -> bool {
+    project_fs::file_exists(project, relative_path)
 }
 
-// プレイヤーステータススクリプト
-pub fn get_player_status_script(project: &str) -> Option<String> {
-    read_script(project_root(project).join("properties").join("playerStatus.py"))
+// マニフェストスクリプト
+pub fn get_manifest_script(project: &str) -> Option<String> {
+    read_script(project, "project.py")
 }
 
-// 敵ステータススクリプト
-pub fn get_enemy_status_script(project: &str) -> Option<String> {
-    read_script(project_root(project).join("properties").join("enemyStatus.py"))
+// プロジェクト内スクリプト
+pub fn get_project_script(project: &str, relative_path: &str) -> Option<String> {
+    if !project_fs::is_safe_relative_path(relative_path) {
+        println!("Warning: script path invalid {}", relative_path);
+        return None;
+    }
+    read_script(project, relative_path)
 }
 
 // 立ち絵スクリプト
@@ -34,12 +40,12 @@ pub fn get_tachie_script(project: &str, script_name: &str) -> Option<String> {
     if script_name.is_empty() {
         return None;
     }
-    read_script(project_root(project).join("tachie").join(format!("{}.py", script_name)))
+    read_script(project, &format!("tachie/{}.py", script_name))
 }
 
 // フェーズAPIスクリプト
 pub fn get_phase_api_script(project: &str) -> Option<String> {
-    read_script(project_root(project).join("phases").join("phase_api.py"))
+    read_script(project, "phases/phase_api.py")
 }
 
 // フェーズスクリプト
@@ -47,37 +53,21 @@ pub fn get_phase_script(project: &str, phase_name: &str) -> Option<String> {
     if phase_name.is_empty() {
         return None;
     }
-    read_script(project_root(project).join("phases").join(format!("{}.py", phase_name)))
+    read_script(project, &format!("phases/{}.py", phase_name))
 }
 
 // フェーズ一覧
 pub fn list_phase_names(project: &str) -> Vec<String> {
-    let phases_dir = project_root(project).join("phases");
-    let entries = match fs::read_dir(phases_dir) {
-        Ok(entries) => entries,
-        Err(_) => return vec![],
-    };
-    let mut names = Vec::new();
-    for entry in entries.flatten() {
-        let path = entry.path();
-        if path.extension().and_then(|ext| ext.to_str()) != Some("py") {
-            continue;
-        }
-        let stem = match path.file_stem().and_then(|stem| stem.to_str()) {
-            Some(stem) => stem,
-            None => continue,
-        };
-        if stem == "phase_api" {
-            continue;
-        }
-        names.push(stem.to_string());
-    }
-    names
+    project_fs::list_files(project, "phases")
+        .into_iter()
+        .filter_map(|name| name.strip_suffix(".py").map(|stem| stem.to_string()))
+        .filter(|stem| stem != "phase_api")
+        .collect()
 }
 
 // 弾幕APIスクリプト
 pub fn get_danmaku_api_script(project: &str) -> Option<String> {
-    read_script(project_root(project).join("danmaku").join("api.py"))
+    read_script(project, "danmaku/api.py")
 }
 
 // 弾幕スクリプト
@@ -85,5 +75,5 @@ pub fn get_danmaku_script(project: &str, script_name: &str) -> Option<String> {
     if script_name.is_empty() {
         return None;
     }
-    read_script(project_root(project).join("danmaku").join(format!("{}.py", script_name)))
+    read_script(project, &format!("danmaku/{}.py", script_name))
 }
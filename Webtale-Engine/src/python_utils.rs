@@ -1,6 +1,8 @@
 // Python値読み取り
-use rustpython_vm::builtins::PyDictRef;
-use rustpython_vm::VirtualMachine;
+use std::collections::HashMap;
+use rustpython_vm::builtins::{PyDict, PyDictRef, PyFloat, PyInt, PyList, PyStr, PyTuple};
+use rustpython_vm::{AsObject, PyObjectRef, VirtualMachine};
+use crate::resources::ScriptValue;
 
 // 文字列取得
 pub fn read_option_string(vm: &VirtualMachine, dict: &PyDictRef, key: &str, label: &str, warn_missing: bool) -> Option<String> {
@@ -77,6 +79,31 @@ pub fn read_option_i32(vm: &VirtualMachine, dict: &PyDictRef, key: &str, label:
     }
 }
 
+// bool取得
+pub fn read_option_bool(vm: &VirtualMachine, dict: &PyDictRef, key: &str, label: &str, warn_missing: bool) -> Option<bool> {
+    match dict.get_item_opt(key, vm) {
+        Ok(Some(value)) => match value.try_into_value(vm) {
+            Ok(result) => Some(result),
+            Err(err) => {
+                vm.print_exception(err.clone());
+                println!("Warning: {} {} {:?}", label, key, err);
+                None
+            }
+        },
+        Ok(None) => {
+            if warn_missing {
+                println!("Warning: {} missing {}", label, key);
+            }
+            None
+        }
+        Err(err) => {
+            vm.print_exception(err.clone());
+            println!("Warning: {} {} {:?}", label, key, err);
+            None
+        }
+    }
+}
+
 // 文字列配列取得
 pub fn read_option_vec_string(vm: &VirtualMachine, dict: &PyDictRef, key: &str, label: &str, warn_missing: bool) -> Option<Vec<String>> {
     match dict.get_item_opt(key, vm) {
@@ -126,3 +153,130 @@ pub fn read_option_vec_f32(vm: &VirtualMachine, dict: &PyDictRef, key: &str, lab
         }
     }
 }
+
+// スクリプト変数変換
+pub fn py_to_script_value(vm: &VirtualMachine, value: &PyObjectRef) -> Option<ScriptValue> {
+    if vm.is_none(value) {
+        return Some(ScriptValue::None);
+    }
+    if value.class().is(vm.ctx.types.bool_type) {
+        return value.clone().try_into_value::<bool>(vm).ok().map(ScriptValue::Bool);
+    }
+    if let Some(int) = value.payload::<PyInt>() {
+        return int.try_to_primitive::<i64>(vm).ok().map(ScriptValue::Int);
+    }
+    if let Some(float) = value.payload::<PyFloat>() {
+        return Some(ScriptValue::Float(float.to_f64()));
+    }
+    if let Some(text) = value.payload::<PyStr>() {
+        return Some(ScriptValue::Str(text.as_str().to_string()));
+    }
+    if let Some(list) = value.payload::<PyList>() {
+        let items: Option<Vec<ScriptValue>> = list.borrow_vec().iter().map(|item| py_to_script_value(vm, item)).collect();
+        return items.map(ScriptValue::List);
+    }
+    if let Some(tuple) = value.payload::<PyTuple>() {
+        let items: Option<Vec<ScriptValue>> = tuple.as_slice().iter().map(|item| py_to_script_value(vm, item)).collect();
+        return items.map(ScriptValue::List);
+    }
+    if let Ok(dict) = value.clone().downcast::<PyDict>() {
+        let mut entries = Vec::new();
+        for (key, item) in &dict {
+            let key: String = key.try_into_value(vm).ok()?;
+            entries.push((key, py_to_script_value(vm, &item)?));
+        }
+        return Some(ScriptValue::Dict(entries));
+    }
+    None
+}
+
+// Python値変換
+pub fn script_value_to_py(vm: &VirtualMachine, value: &ScriptValue) -> PyObjectRef {
+    match value {
+        ScriptValue::None => vm.ctx.none(),
+        ScriptValue::Bool(value) => vm.ctx.new_bool(*value).into(),
+        ScriptValue::Int(value) => vm.ctx.new_int(*value).into(),
+        ScriptValue::Float(value) => vm.ctx.new_float(*value).into(),
+        ScriptValue::Str(value) => vm.ctx.new_str(value.as_str()).into(),
+        ScriptValue::List(items) => vm.ctx.new_list(items.iter().map(|item| script_value_to_py(vm, item)).collect()).into(),
+        ScriptValue::Dict(entries) => {
+            let dict = vm.ctx.new_dict();
+            for (key, item) in entries {
+                let _ = dict.set_item(key.as_str(), script_value_to_py(vm, item), vm);
+            }
+            dict.into()
+        }
+    }
+}
+
+// 変数辞書生成
+pub fn vars_to_py_dict(vm: &VirtualMachine, vars: &HashMap<String, ScriptValue>) -> PyDictRef {
+    let dict = vm.ctx.new_dict();
+    for (key, value) in vars {
+        let _ = dict.set_item(key.as_str(), script_value_to_py(vm, value), vm);
+    }
+    dict
+}
+
+// 変数辞書読み取り
+pub fn py_dict_to_vars(vm: &VirtualMachine, dict: &PyDictRef, label: &str) -> HashMap<String, ScriptValue> {
+    let mut vars = HashMap::new();
+    for (key, value) in dict {
+        let key: String = match key.try_into_value(vm) {
+            Ok(key) => key,
+            Err(err) => {
+                vm.print_exception(err.clone());
+                println!("Warning: {} key must be str", label);
+                continue;
+            }
+        };
+        match py_to_script_value(vm, &value) {
+            Some(value) => {
+                vars.insert(key, value);
+            }
+            None => println!("Warning: {} {} unsupported value", label, key),
+        }
+    }
+    vars
+}
+
+// 関数の引数数
+pub fn callable_arg_count(vm: &VirtualMachine, func: &PyObjectRef) -> Option<usize> {
+    let code = func.get_attr("__code__", vm).ok()?;
+    code.get_attr("co_argcount", vm).ok()?.try_into_value(vm).ok()
+}
+
+// 数値変換(intも可)
+pub fn py_to_f32(vm: &VirtualMachine, value: &PyObjectRef) -> Option<f32> {
+    if let Ok(result) = value.clone().try_into_value::<f64>(vm) {
+        return Some(result as f32);
+    }
+    value.clone().try_into_value::<i64>(vm).ok().map(|result| result as f32)
+}
+
+// テスト用の式評価
+#[cfg(test)]
+pub fn eval_test_expr<R>(source: &str, f: impl FnOnce(&VirtualMachine, PyObjectRef) -> R) -> R {
+    rustpython_vm::Interpreter::without_stdlib(Default::default()).enter(|vm| {
+        let code = vm.compile(source, rustpython_vm::compiler::Mode::Eval, "<test>".to_string()).expect("compile test expr");
+        let value = vm.run_code_obj(code, vm.new_scope_with_builtins()).expect("run test expr");
+        f(vm, value)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn py_to_f32_accepts_int_and_float() {
+        assert_eq!(eval_test_expr("3", |vm, value| py_to_f32(vm, &value)), Some(3.0));
+        assert_eq!(eval_test_expr("-2.5", |vm, value| py_to_f32(vm, &value)), Some(-2.5));
+    }
+
+    #[test]
+    fn py_to_f32_rejects_non_numbers() {
+        assert_eq!(eval_test_expr("'3'", |vm, value| py_to_f32(vm, &value)), None);
+        assert_eq!(eval_test_expr("None", |vm, value| py_to_f32(vm, &value)), None);
+    }
+}
@@ -1,24 +1,237 @@
 use bevy::prelude::*;
 use rand::Rng;
+use rustpython_vm::compiler::Mode;
 use rustpython_vm::function::ArgIntoFloat;
+use rustpython_vm::import::import_codeobj;
 use rustpython_vm::Interpreter;
 use rustpython_vm::PyObjectRef;
 use evalexpr::Node;
 use std::collections::HashMap;
+use crate::components::{GravityDirection, SoulMode};
+use crate::constants::{DEFAULT_PROJECT_NAME, PLATFORM_THICKNESS, PROJECT_ENV_VAR};
+use crate::project_archive;
+use crate::python_scripts;
+
+// 現在のプロジェクト
+#[derive(Resource, Clone, Debug)]
+pub struct CurrentProject {
+    pub name: String,
+}
+
+impl CurrentProject {
+    // 起動時プロジェクト決定
+    pub fn from_startup() -> Self {
+        let requested = project_from_args()
+            .or_else(project_from_env)
+            .or_else(project_from_url);
+        let requested = requested.and_then(|name| {
+            if !project_archive::is_archive_path(&name) {
+                return Some(name);
+            }
+            match project_archive::mount_archive_file(&name) {
+                Ok(project_name) => Some(project_name),
+                Err(message) => {
+                    println!("Warning: {}", message);
+                    None
+                }
+            }
+        });
+        let name = match requested {
+            Some(name) if python_scripts::project_exists(&name) => name,
+            Some(name) => {
+                println!("Warning: project missing projects/{}", name);
+                DEFAULT_PROJECT_NAME.to_string()
+            }
+            None => DEFAULT_PROJECT_NAME.to_string(),
+        };
+        Self { name }
+    }
+}
+
+// 引数指定
+fn project_from_args() -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--project" {
+            return args.next();
+        }
+        if let Some(value) = arg.strip_prefix("--project=") {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+// 環境変数指定
+fn project_from_env() -> Option<String> {
+    std::env::var(PROJECT_ENV_VAR).ok().filter(|value| !value.is_empty())
+}
+
+// URL指定
+#[cfg(target_arch = "wasm32")]
+fn project_from_url() -> Option<String> {
+    let search = web_sys::window()?.location().search().ok()?;
+    search.trim_start_matches('?').split('&').find_map(|pair| {
+        let (key, value) = pair.split_once('=')?;
+        if key == "project" && !value.is_empty() {
+            Some(value.to_string())
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn project_from_url() -> Option<String> {
+    None
+}
+
+// プロジェクトマニフェスト
+#[derive(Resource, Clone, Debug)]
+pub struct ProjectManifest {
+    pub name: String,
+    pub author: String,
+    pub engine_version: String,
+    pub player_script: String,
+    pub enemy_script: String,
+    pub encounter: Vec<String>,
+    pub item_script: String,
+    pub initial_phase: String,
+    pub asset_roots: Vec<String>,
+    pub font_main: String,
+    pub font_dialog: String,
+    pub font_hp_label: String,
+    pub font_damage: String,
+    pub music: Option<String>,
+    pub hurt_sound: Option<String>,
+    pub flee_chance: f32,
+    pub flee_chance_per_turn: f32,
+    pub damage_script: Option<String>,
+    pub damage_formula: Option<String>,
+}
+
+impl ProjectManifest {
+    // 従来レイアウト
+    pub fn default_layout(project_name: &str) -> Self {
+        Self {
+            name: project_name.to_string(),
+            author: String::new(),
+            engine_version: String::new(),
+            player_script: "properties/playerStatus.py".to_string(),
+            enemy_script: "properties/enemyStatus.py".to_string(),
+            encounter: vec![],
+            item_script: "properties/item.py".to_string(),
+            initial_phase: String::new(),
+            asset_roots: vec!["assets".to_string()],
+            font_main: "font/Mars_Needs_Cunnilingus.ttf".to_string(),
+            font_dialog: "font/8bitOperatorPlus-Bold.ttf".to_string(),
+            font_hp_label: "font/8-BIT_WO.ttf".to_string(),
+            font_damage: "font/hachicro.TTF".to_string(),
+            music: None,
+            hurt_sound: None,
+            flee_chance: 0.5,
+            flee_chance_per_turn: 0.1,
+            damage_script: None,
+            damage_formula: None,
+        }
+    }
+
+    // 敵スクリプト一覧
+    pub fn enemy_scripts(&self) -> Vec<String> {
+        if self.encounter.is_empty() {
+            vec![self.enemy_script.clone()]
+        } else {
+            self.encounter.clone()
+        }
+    }
+}
+
+// アイテム種別
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ItemKind {
+    Consumable,
+    Weapon,
+    Armor,
+}
+
+impl ItemKind {
+    // 種別名変換
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "consumable" => Some(ItemKind::Consumable),
+            "weapon" => Some(ItemKind::Weapon),
+            "armor" => Some(ItemKind::Armor),
+            _ => None,
+        }
+    }
+
+    // 装備品判定
+    pub fn is_equipment(self) -> bool {
+        self != ItemKind::Consumable
+    }
+}
+
+// 攻撃バー種別
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttackBarStyle {
+    Normal,
+    Fast,
+    Slow,
+    Reverse,
+}
+
+impl AttackBarStyle {
+    // 種別名変換
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "normal" => Some(AttackBarStyle::Normal),
+            "fast" => Some(AttackBarStyle::Fast),
+            "slow" => Some(AttackBarStyle::Slow),
+            "reverse" => Some(AttackBarStyle::Reverse),
+            _ => None,
+        }
+    }
+
+    // バー速度(負は右から左)
+    pub fn speed(self) -> f32 {
+        match self {
+            AttackBarStyle::Normal => 420.0,
+            AttackBarStyle::Fast => 630.0,
+            AttackBarStyle::Slow => 280.0,
+            AttackBarStyle::Reverse => -420.0,
+        }
+    }
+}
 
 // アイテム情報
 #[derive(Clone, Debug)]
 pub struct ItemInfo {
+    pub kind: ItemKind,
     pub heal_amount: i32,
     pub attack: i32,
     pub defense: i32,
     pub text: String,
+    pub bar_style: AttackBarStyle,
+    pub damage_multiplier: f32,
+    pub use_callback: Option<PyObjectRef>,
+    pub consume: bool,
 }
 
 // アイテム辞書
 #[derive(Resource, Default)]
 pub struct ItemDictionary(pub HashMap<String, ItemInfo>);
 
+impl ItemDictionary {
+    // 装備中の同種アイテム
+    pub fn equipped(&self, player_state: &PlayerState, kind: ItemKind) -> Option<(usize, &ItemInfo)> {
+        player_state
+            .equipped_items
+            .iter()
+            .enumerate()
+            .find_map(|(index, name)| self.0.get(name).filter(|info| info.kind == kind).map(|info| (index, info)))
+    }
+}
+
 // 弾幕式
 #[derive(Clone)]
 pub struct ExprAssignment {
@@ -40,6 +253,152 @@ pub struct DanmakuScripts {
     pub rust_specs: HashMap<String, RustSimSpec>,
 }
 
+// 弾幕スケジュール
+#[derive(Clone)]
+pub struct WaveEntry {
+    pub time: f32,
+    pub args: Option<PyObjectRef>,
+}
+
+// 進行中の弾幕
+#[derive(Resource, Default)]
+pub struct DanmakuWave {
+    pub active: bool,
+    pub script_name: String,
+    pub module: Option<PyObjectRef>,
+    pub texture_path: String,
+    pub rust_sim: Option<RustSimSpec>,
+    pub box_rect: Option<Rect>,
+    pub soul_start: Option<Vec2>,
+    pub duration: f32,
+    pub end_when_clear: bool,
+    pub count: i32,
+    pub angle: f32,
+    pub spread: f32,
+    pub schedule: Vec<WaveEntry>,
+    pub next_entry: usize,
+    pub bursts: i32,
+    pub spawned: usize,
+    pub elapsed: f32,
+    pub on_tick: Option<PyObjectRef>,
+    pub soul_mode: Option<SoulMode>,
+    pub gravity: Option<GravityDirection>,
+    pub platforms: Vec<BoxPlatform>,
+}
+
+// ダメージ計算式
+#[derive(Resource, Default)]
+pub struct DamageModel {
+    pub hook: Option<PyObjectRef>,
+    pub formula: Option<Node>,
+}
+
+// フェーズスクリプトキャッシュ
+#[derive(Resource, Default)]
+pub struct PhaseScripts {
+    pub api: Option<PyObjectRef>,
+    pub modules: HashMap<String, PyObjectRef>,
+}
+
+// スクリプト監視
+#[derive(Resource)]
+pub struct ScriptHotReload {
+    pub timer: Timer,
+    pub project: String,
+    pub modified: HashMap<String, std::time::SystemTime>,
+    pub last_error: Option<String>,
+}
+
+impl Default for ScriptHotReload {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(0.5, TimerMode::Repeating),
+            project: String::new(),
+            modified: HashMap::new(),
+            last_error: None,
+        }
+    }
+}
+
+// ボックス操作API
+const BOX_MODULE_SOURCE: &str = r#"
+_commands = []
+_initial_state = {"left": 0.0, "top": 0.0, "right": 0.0, "bottom": 0.0, "angle": 0.0, "platforms": []}
+_state = dict(_initial_state)
+
+def _reset():
+    _commands.clear()
+    _state.clear()
+    _state.update(_initial_state)
+
+def _push(kind, duration, easing, values):
+    command = {"type": kind, "duration": float(duration), "easing": easing}
+    command.update({key: float(value) for key, value in values.items()})
+    _commands.append(command)
+
+def moveTo(x, y, duration=0.0, easing="linear"):
+    _push("moveTo", duration, easing, {"x": x, "y": y})
+
+def resize(width, height, duration=0.0, easing="linear"):
+    _push("resize", duration, easing, {"width": width, "height": height})
+
+def setRect(left, top, right, bottom, duration=0.0, easing="linear"):
+    _push("setRect", duration, easing, {"left": left, "top": top, "right": right, "bottom": bottom})
+
+def rotate(angle, duration=0.0, easing="linear"):
+    _push("rotate", duration, easing, {"angle": angle})
+
+def getRect():
+    return [_state["left"], _state["top"], _state["right"], _state["bottom"]]
+
+def getCenter():
+    return [(_state["left"] + _state["right"]) / 2.0, (_state["top"] + _state["bottom"]) / 2.0]
+
+def getAngle():
+    return _state["angle"]
+
+def setPlatforms(platforms):
+    _commands.append({"type": "setPlatforms", "platforms": list(platforms)})
+
+def addPlatform(x, y, width, vx=0.0, vy=0.0, vertical=False):
+    _commands.append({"type": "addPlatform", "x": x, "y": y, "width": width, "vx": vx, "vy": vy, "vertical": vertical})
+
+def clearPlatforms():
+    setPlatforms([])
+
+def getPlatforms():
+    return [list(platform) for platform in _state["platforms"]]
+"#;
+
+const SOUL_MODULE_SOURCE: &str = r#"
+_commands = []
+_initial_state = {"mode": "red", "gravity": "down", "grounded": False}
+_state = dict(_initial_state)
+
+def _reset():
+    _commands.clear()
+    _state.clear()
+    _state.update(_initial_state)
+
+def setMode(mode, gravity=None):
+    _commands.append({"type": "setMode", "mode": mode, "gravity": gravity})
+
+def setGravity(direction):
+    _commands.append({"type": "setGravity", "gravity": direction})
+
+def slam(direction=None):
+    _commands.append({"type": "slam", "gravity": direction})
+
+def getMode():
+    return _state["mode"]
+
+def getGravity():
+    return _state["gravity"]
+
+def isGrounded():
+    return _state["grounded"]
+"#;
+
 // Python実行環境
 pub struct PythonRuntime {
     pub interpreter: Interpreter,
@@ -75,17 +434,45 @@ impl Default for PythonRuntime {
             if let Ok(modules) = vm.sys_module.get_attr("modules", vm) {
                 let _ = modules.set_item("random", module.into(), vm);
             }
+
+            // ボックス・ソウル操作モジュール
+            for (source, name) in [(BOX_MODULE_SOURCE, "box"), (SOUL_MODULE_SOURCE, "soul")] {
+                match vm.compile(source, Mode::Exec, format!("{}.py", name)) {
+                    Ok(code_obj) => {
+                        if let Err(err) = import_codeobj(vm, name, code_obj, true) {
+                            vm.print_exception(err.clone());
+                        }
+                    }
+                    Err(err) => println!("Warning: python compile {}.py {:?}", name, err),
+                }
+            }
         });
         Self { interpreter }
     }
 }
 
+impl PythonRuntime {
+    // ボックス・ソウル命令の破棄と状態の初期化
+    pub fn reset_script_modules(&self) {
+        self.interpreter.enter(|vm| {
+            for name in ["box", "soul"] {
+                let result = vm.import(name, 0).and_then(|module| module.get_attr("_reset", vm)).and_then(|reset| reset.call((), vm));
+                if let Err(err) = result {
+                    vm.print_exception(err.clone());
+                }
+            }
+        });
+    }
+}
+
 // プレイヤーデータ
 #[derive(Resource)]
 pub struct PlayerState {
     pub hp: f32,
     pub max_hp: f32,
     pub lv: i32,
+    pub exp: i32,
+    pub gold: i32,
     pub name: String,
     pub speed: f32,
     pub attack: f32,
@@ -96,13 +483,93 @@ pub struct PlayerState {
     pub equipped_items: Vec<String>,
 }
 
+impl PlayerState {
+    // 経験値加算
+    pub fn add_exp(&mut self, amount: i32, lv_table: &LvTable) -> bool {
+        self.exp += amount.max(0);
+        let old_lv = self.lv;
+        while self.lv < lv_table.max_lv() && self.exp >= lv_table.stats(self.lv + 1).exp {
+            self.lv += 1;
+        }
+        if self.lv == old_lv {
+            return false;
+        }
+        let stats = lv_table.stats(self.lv);
+        let hp_gain = stats.max_hp - self.max_hp;
+        self.max_hp = stats.max_hp;
+        self.hp = (self.hp + hp_gain.max(0.0)).min(self.max_hp);
+        self.attack = stats.attack;
+        self.defense = stats.defense;
+        true
+    }
+
+    // LV設定
+    pub fn set_lv(&mut self, lv: i32, lv_table: &LvTable) {
+        self.lv = lv.clamp(1, lv_table.max_lv());
+        let stats = lv_table.stats(self.lv);
+        self.exp = stats.exp;
+        self.max_hp = stats.max_hp;
+        self.hp = stats.max_hp;
+        self.attack = stats.attack;
+        self.defense = stats.defense;
+    }
+}
+
+// LV別ステータス
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LvStats {
+    pub exp: i32,
+    pub max_hp: f32,
+    pub attack: f32,
+    pub defense: f32,
+}
+
+// LV表
+#[derive(Resource, Clone)]
+pub struct LvTable {
+    pub levels: Vec<LvStats>,
+}
+
+impl Default for LvTable {
+    fn default() -> Self {
+        let exp = [
+            0, 10, 30, 70, 120, 200, 300, 500, 800, 1200,
+            1700, 2500, 3500, 5000, 7000, 10000, 15000, 25000, 50000, 99999,
+        ];
+        let levels = exp.iter().enumerate().map(|(index, exp)| {
+            let lv = index as i32 + 1;
+            LvStats {
+                exp: *exp,
+                max_hp: if lv >= 20 { 99.0 } else { 16.0 + lv as f32 * 4.0 },
+                attack: 20.0 + (lv - 1) as f32 * 2.0,
+                defense: ((lv - 1) / 4) as f32,
+            }
+        }).collect();
+        Self { levels }
+    }
+}
+
+impl LvTable {
+    // 最大LV
+    pub fn max_lv(&self) -> i32 {
+        self.levels.len().max(1) as i32
+    }
+
+    // LV別ステータス
+    pub fn stats(&self, lv: i32) -> LvStats {
+        let index = (lv - 1).clamp(0, self.max_lv() - 1) as usize;
+        self.levels.get(index).copied().unwrap_or(LvStats { exp: 0, max_hp: 20.0, attack: 20.0, defense: 0.0 })
+    }
+}
+
 // 敵データ
-#[derive(Resource)]
 pub struct EnemyState {
     pub hp: i32,
     pub max_hp: i32,
     pub atk: i32,
     pub def: i32,
+    pub exp: i32,
+    pub gold: i32,
     pub name: String,
     pub dialog_text: String,
     pub act_commands: Vec<String>,
@@ -121,6 +588,115 @@ pub struct EnemyState {
     pub bubble_texture: String,
     pub bubble_message_override: Option<String>,
     pub bubble_pos_override: Option<Vec2>,
+    pub phase_name: String,
+    pub phase_turn: i32,
+    pub phase_vars: HashMap<String, HashMap<String, ScriptValue>>,
+    pub spareable: bool,
+    pub spared: bool,
+    pub fleeable: bool,
+    pub spare_acts: HashMap<String, i32>,
+    pub act_counts: HashMap<String, i32>,
+    pub soul_mode: SoulMode,
+    pub soul_gravity: GravityDirection,
+}
+
+impl EnemyState {
+    // 戦闘参加判定
+    pub fn is_active(&self) -> bool {
+        self.hp > 0 && !self.spared
+    }
+
+    // ACT見逃し条件
+    pub fn record_act(&mut self, command: &str) {
+        let count = self.act_counts.entry(command.to_string()).or_insert(0);
+        *count += 1;
+        if let Some(required) = self.spare_acts.get(command) {
+            if *count >= *required {
+                self.spareable = true;
+            }
+        }
+    }
+}
+
+// 敵編成
+#[derive(Resource, Default)]
+pub struct Encounter {
+    pub enemies: Vec<EnemyState>,
+    pub target: usize,
+}
+
+impl Encounter {
+    // 生存敵番号
+    pub fn active_indices(&self) -> Vec<usize> {
+        self.enemies.iter().enumerate().filter(|(_, enemy)| enemy.is_active()).map(|(index, _)| index).collect()
+    }
+
+    // 選択中の敵
+    pub fn target_enemy(&self) -> Option<&EnemyState> {
+        self.enemies.get(self.target)
+    }
+
+    pub fn target_enemy_mut(&mut self) -> Option<&mut EnemyState> {
+        self.enemies.get_mut(self.target)
+    }
+
+    // 逃走可否
+    pub fn can_flee(&self) -> bool {
+        self.enemies.iter().filter(|enemy| enemy.is_active()).all(|enemy| enemy.fleeable)
+    }
+
+    // 戦闘結果集計
+    pub fn battle_result(&self) -> BattleResult {
+        let spared: Vec<String> = self.enemies.iter().filter(|enemy| enemy.spared).map(|enemy| enemy.name.clone()).collect();
+        let killed: Vec<String> = self.enemies.iter().filter(|enemy| enemy.hp <= 0).map(|enemy| enemy.name.clone()).collect();
+        let outcome = if killed.is_empty() { BattleOutcome::Spared } else { BattleOutcome::Killed };
+        let exp = self.enemies.iter().filter(|enemy| enemy.hp <= 0).map(|enemy| enemy.exp).sum();
+        let gold = self.enemies.iter().filter(|enemy| !enemy.is_active()).map(|enemy| enemy.gold).sum();
+        BattleResult { outcome, spared, killed, exp, gold }
+    }
+}
+
+// 戦闘結果種別
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BattleOutcome {
+    Spared,
+    Killed,
+    Fled,
+}
+
+// 戦闘結果
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BattleResult {
+    pub outcome: BattleOutcome,
+    pub spared: Vec<String>,
+    pub killed: Vec<String>,
+    pub exp: i32,
+    pub gold: i32,
+}
+
+// 戦闘終了イベント
+#[derive(Event, Clone, Debug)]
+pub struct BattleFinished {
+    pub result: BattleResult,
+}
+
+// 被弾イベント
+#[derive(Event, Clone, Debug)]
+pub struct SoulHitEvent {
+    pub damage: i32,
+}
+
+// 回復弾イベント
+#[derive(Event, Clone, Debug)]
+pub struct SoulHealEvent {
+    pub amount: i32,
+}
+
+// 画面揺れ
+#[derive(Resource, Default)]
+pub struct ScreenShake {
+    pub timer: f32,
+    pub intensity: f32,
 }
 
 // メニュー操作
@@ -139,9 +715,7 @@ pub struct MenuRenderKey {
     pub menu_coords: Vec<i32>,
     pub item_page: usize,
     pub dialog_text: String,
-    pub enemy_name: String,
-    pub enemy_hp: i32,
-    pub enemy_max_hp: i32,
+    pub enemies: Vec<(String, i32, i32, bool)>,
     pub act_commands: Vec<String>,
     pub inventory: Vec<String>,
 }
@@ -162,6 +736,8 @@ pub enum MainFightState {
     PlayerAttackBar,
     PlayerAttackResolve,
     PlayerDefeated,
+    Victory,
+    Fled,
 }
 
 impl Default for MainFightState {
@@ -188,21 +764,163 @@ impl Default for MessageFightState {
 pub struct CombatState {
     pub mn_fight: MainFightState,
     pub my_fight: MessageFightState,
-    pub phase_name: String,
-    pub phase_turn: i32,
     pub turn_count: i32,
     pub turn_timer: f32,
     pub bubble_timer: Timer,
     pub damage_display_timer: Timer,
     pub last_player_action: String,
     pub last_act_command: Option<String>,
+    pub attacking_enemy: usize,
+    pub attack_bonus: f32,
+    pub defense_bonus: f32,
+    pub heal_over_time: Vec<HealOverTime>,
+    pub battle_result: Option<BattleResult>,
+    pub battle_finished: bool,
+    pub battle_vars: HashMap<String, ScriptValue>,
+}
+
+// 継続回復
+#[derive(Clone, Debug)]
+pub struct HealOverTime {
+    pub amount: i32,
+    pub turns: i32,
+    pub last_turn: i32,
+}
+
+// スクリプト変数値
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScriptValue {
+    None,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    List(Vec<ScriptValue>),
+    Dict(Vec<(String, ScriptValue)>),
+}
+
+impl std::fmt::Display for ScriptValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScriptValue::None => write!(f, "None"),
+            ScriptValue::Bool(value) => write!(f, "{}", if *value { "True" } else { "False" }),
+            ScriptValue::Int(value) => write!(f, "{}", value),
+            ScriptValue::Float(value) => write!(f, "{}", value),
+            ScriptValue::Str(value) => write!(f, "{:?}", value),
+            ScriptValue::List(items) => {
+                let parts: Vec<String> = items.iter().map(|item| item.to_string()).collect();
+                write!(f, "[{}]", parts.join(", "))
+            }
+            ScriptValue::Dict(entries) => {
+                let parts: Vec<String> = entries.iter().map(|(key, value)| format!("{:?}: {}", key, value)).collect();
+                write!(f, "{{{}}}", parts.join(", "))
+            }
+        }
+    }
 }
 
 // バトルボックス
-#[derive(Resource)]
+#[derive(Resource, Default)]
 pub struct BattleBox {
     pub current: Rect,
     pub target: Rect,
+    pub rotation: f32,
+    pub rect_tween: Option<BoxTween<Rect>>,
+    pub rotation_tween: Option<BoxTween<f32>>,
+    pub platforms: Vec<BoxPlatform>,
+    pub platform_revision: u32,
+}
+
+impl BattleBox {
+    // 足場の置き換え(乗っている足場の番号を無効化)
+    pub fn set_platforms(&mut self, platforms: Vec<BoxPlatform>) {
+        self.platforms = platforms;
+        self.platform_revision = self.platform_revision.wrapping_add(1);
+    }
+}
+
+// ボックス内の足場(ボックス左上基準)
+#[derive(Clone, Copy, Debug)]
+pub struct BoxPlatform {
+    pub position: Vec2,
+    pub width: f32,
+    pub velocity: Vec2,
+    pub vertical: bool,
+}
+
+impl BoxPlatform {
+    // ボックス中心基準の矩形(上が正)
+    pub fn local_rect(&self, box_half: Vec2) -> Rect {
+        let size = if self.vertical { Vec2::new(PLATFORM_THICKNESS, self.width) } else { Vec2::new(self.width, PLATFORM_THICKNESS) };
+        let left = self.position.x - box_half.x;
+        let top = box_half.y - self.position.y;
+        Rect::new(left, top - size.y, left + size.x, top)
+    }
+}
+
+// ボックス補間方式
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BoxEasing {
+    #[default]
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl BoxEasing {
+    // 名前から変換
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "linear" => Some(Self::Linear),
+            "easeIn" => Some(Self::EaseIn),
+            "easeOut" => Some(Self::EaseOut),
+            "easeInOut" => Some(Self::EaseInOut),
+            _ => None,
+        }
+    }
+
+    // 補間率
+    pub fn apply(self, t: f32) -> f32 {
+        match self {
+            Self::Linear => t,
+            Self::EaseIn => t * t,
+            Self::EaseOut => t * (2.0 - t),
+            Self::EaseInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    -1.0 + (4.0 - 2.0 * t) * t
+                }
+            }
+        }
+    }
+}
+
+// ボックス補間
+#[derive(Clone, Copy, Debug)]
+pub struct BoxTween<T> {
+    pub from: T,
+    pub to: T,
+    pub elapsed: f32,
+    pub duration: f32,
+    pub easing: BoxEasing,
+}
+
+impl<T> BoxTween<T> {
+    // 経過時間を進めて補間率を返す
+    pub fn advance(&mut self, dt: f32) -> f32 {
+        self.elapsed += dt;
+        if self.duration <= 0.0 {
+            return 1.0;
+        }
+        self.easing.apply((self.elapsed / self.duration).min(1.0))
+    }
+
+    // 完了判定
+    pub fn finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
 }
 
 // フォント管理
@@ -226,6 +944,9 @@ pub enum EditorTab {
 #[derive(Resource, Default)]
 pub struct EditorState {
     pub current_tab: EditorTab,
+    pub project_selection: String,
+    pub archive_path: String,
+    pub archive_status: String,
 }
 
 // エディタプレビュー
@@ -235,3 +956,126 @@ pub struct EditorPreviewTexture(pub Handle<Image>);
 // 弾幕プレビュー
 #[derive(Resource, Default)]
 pub struct DanmakuPreviewTexture(pub Handle<Image>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_player(lv_table: &LvTable) -> PlayerState {
+        let mut player = PlayerState {
+            hp: 0.0,
+            max_hp: 0.0,
+            lv: 1,
+            exp: 0,
+            gold: 0,
+            name: String::new(),
+            speed: 0.0,
+            attack: 0.0,
+            defense: 0.0,
+            invincibility_duration: 0.0,
+            invincibility_timer: 0.0,
+            inventory: vec![],
+            equipped_items: vec![],
+        };
+        player.set_lv(1, lv_table);
+        player
+    }
+
+    #[test]
+    fn default_lv_table_matches_undertale() {
+        let table = LvTable::default();
+        assert_eq!(table.max_lv(), 20);
+        assert_eq!(table.stats(1), LvStats { exp: 0, max_hp: 20.0, attack: 20.0, defense: 0.0 });
+        assert_eq!(table.stats(5), LvStats { exp: 120, max_hp: 36.0, attack: 28.0, defense: 1.0 });
+        assert_eq!(table.stats(20).max_hp, 99.0);
+        // 範囲外は端の行
+        assert_eq!(table.stats(0), table.stats(1));
+        assert_eq!(table.stats(99), table.stats(20));
+        assert_eq!(LvTable { levels: vec![] }.stats(3), LvStats { exp: 0, max_hp: 20.0, attack: 20.0, defense: 0.0 });
+    }
+
+    #[test]
+    fn add_exp_levels_up_and_keeps_damage() {
+        let table = LvTable::default();
+        let mut player = test_player(&table);
+        player.hp = 10.0;
+        assert!(!player.add_exp(5, &table));
+        assert_eq!((player.lv, player.exp), (1, 5));
+        // 2段階上がると最大HPの増加分だけ回復
+        assert!(player.add_exp(25, &table));
+        assert_eq!((player.lv, player.exp), (3, 30));
+        assert_eq!((player.max_hp, player.hp, player.attack), (28.0, 18.0, 24.0));
+        assert!(!player.add_exp(-100, &table));
+        assert_eq!(player.exp, 30);
+    }
+
+    #[test]
+    fn set_lv_clamps_and_fills_hp() {
+        let table = LvTable::default();
+        let mut player = test_player(&table);
+        player.set_lv(25, &table);
+        assert_eq!((player.lv, player.exp, player.max_hp, player.hp), (20, 99999, 99.0, 99.0));
+        player.set_lv(0, &table);
+        assert_eq!((player.lv, player.hp), (1, 20.0));
+    }
+
+    fn test_enemy(name: &str, hp: i32, spared: bool) -> EnemyState {
+        EnemyState { name: name.to_string(), hp, spared, ..crate::systems::setup::default_enemy_state() }
+    }
+
+    #[test]
+    fn battle_result_lists_spared_and_killed() {
+        let encounter = Encounter { enemies: vec![test_enemy("Froggit", 0, false), test_enemy("Whimsun", 10, true)], target: 0 };
+        let result = encounter.battle_result();
+        assert_eq!(result.outcome, BattleOutcome::Killed);
+        assert_eq!(result.spared, vec!["Whimsun".to_string()]);
+        assert_eq!(result.killed, vec!["Froggit".to_string()]);
+
+        let encounter = Encounter { enemies: vec![test_enemy("Whimsun", 10, true)], target: 0 };
+        let result = encounter.battle_result();
+        assert_eq!(result.outcome, BattleOutcome::Spared);
+        assert!(result.killed.is_empty());
+    }
+
+    #[test]
+    fn battle_result_sums_exp_and_gold() {
+        let mut killed = test_enemy("Froggit", 0, false);
+        killed.exp = 3;
+        killed.gold = 2;
+        let mut spared = test_enemy("Whimsun", 10, true);
+        spared.exp = 5;
+        spared.gold = 4;
+        let mut alive = test_enemy("Moldsmal", 10, false);
+        alive.exp = 7;
+        alive.gold = 6;
+        let result = Encounter { enemies: vec![killed, spared, alive], target: 0 }.battle_result();
+        // EXPは倒した敵のみ、ゴールドは見逃した敵も含む
+        assert_eq!((result.exp, result.gold), (3, 6));
+    }
+
+    #[test]
+    fn box_easing_curves() {
+        for easing in [BoxEasing::Linear, BoxEasing::EaseIn, BoxEasing::EaseOut, BoxEasing::EaseInOut] {
+            assert_eq!(easing.apply(0.0), 0.0);
+            assert_eq!(easing.apply(1.0), 1.0);
+        }
+        assert_eq!(BoxEasing::EaseIn.apply(0.5), 0.25);
+        assert_eq!(BoxEasing::EaseOut.apply(0.5), 0.75);
+        assert_eq!(BoxEasing::EaseInOut.apply(0.5), 0.5);
+        assert_eq!(BoxEasing::from_name("easeInOut"), Some(BoxEasing::EaseInOut));
+        assert_eq!(BoxEasing::from_name("bounce"), None);
+    }
+
+    #[test]
+    fn box_tween_advances_and_clamps() {
+        let mut tween = BoxTween { from: 0.0, to: 10.0, elapsed: 0.0, duration: 2.0, easing: BoxEasing::Linear };
+        assert_eq!(tween.advance(0.5), 0.25);
+        assert!(!tween.finished());
+        assert_eq!(tween.advance(2.0), 1.0);
+        assert!(tween.finished());
+
+        let mut instant = BoxTween { from: 0.0, to: 10.0, elapsed: 0.0, duration: 0.0, easing: BoxEasing::EaseIn };
+        assert_eq!(instant.advance(0.0), 1.0);
+        assert!(instant.finished());
+    }
+}
@@ -4,6 +4,7 @@ use bevy_egui::EguiContexts;
 use crate::components::*;
 use crate::resources::*;
 use crate::constants::*;
+use crate::systems::combat::{calculate_damage, Combatant, DamageKind};
 use crate::systems::phase;
 
 // 攻撃バー
@@ -13,9 +14,13 @@ pub fn attack_bar_update(
     input: Res<ButtonInput<KeyCode>>,
     mut combat_state: ResMut<CombatState>,
     player_state: Res<PlayerState>,
+    item_dictionary: Res<ItemDictionary>,
+    damage_model: Res<DamageModel>,
+    python_runtime: NonSend<PythonRuntime>,
+    encounter: Res<Encounter>,
     asset_server: Res<AssetServer>,
-    mut query: Query<(Entity, &mut Transform, &mut AttackBar, &mut Sprite)>,
-    enemy_query: Query<&Transform, (With<EnemyBody>, Without<AttackBar>)>,
+    mut query: Query<(Entity, &mut Transform, &mut AttackBar, &mut Sprite), Without<EnemyBody>>,
+    enemy_query: Query<(&EnemyBody, &Transform), Without<EnemyHead>>,
     mut egui_contexts: EguiContexts,
     editor_query: Query<Entity, With<EditorWindow>>,
 ) {
@@ -37,8 +42,11 @@ pub fn attack_bar_update(
             }
 
             let box_center_x = gml_to_bevy(32.0 + (602.0-32.0)/2.0, 0.0).x;
-            let miss_threshold = box_center_x + 280.0;
-            let auto_press = transform.translation.x > miss_threshold;
+            let auto_press = if bar.speed < 0.0 {
+                transform.translation.x < box_center_x - 280.0
+            } else {
+                transform.translation.x > box_center_x + 280.0
+            };
 
             if input.just_pressed(KeyCode::KeyZ) || auto_press {
                 if auto_press {
@@ -51,21 +59,31 @@ pub fn attack_bar_update(
                 
                 let distance = (transform.translation.x - box_center_x).abs();
                 
-                let base_damage = player_state.attack;
-                
-                let damage = if distance < 12.0 {
-                    (base_damage * 2.2) as i32 
+                let weapon_multiplier = item_dictionary.equipped(&player_state, ItemKind::Weapon).map(|(_, info)| info.damage_multiplier).unwrap_or(1.0);
+                let multiplier = weapon_multiplier * if distance < 12.0 {
+                    2.2
                 } else {
                     let stretch = (280.0 - distance).max(0.0) / 280.0;
-                    (base_damage * stretch * 2.0) as i32
+                    stretch * 2.0
                 };
-
-                let enemy_pos = if let Ok(e_trans) = enemy_query.get_single() {
-                    e_trans.translation
-                } else {
-                    gml_to_bevy(320.0, 136.0)
+                let damage = match encounter.target_enemy() {
+                    Some(enemy_state) => calculate_damage(
+                        &damage_model,
+                        &python_runtime,
+                        DamageKind::PlayerAttack,
+                        &Combatant::from_player(&player_state, &item_dictionary).with_bonus(combat_state.attack_bonus, combat_state.defense_bonus),
+                        &Combatant::from_enemy(enemy_state),
+                        multiplier,
+                    ),
+                    None => 0,
                 };
 
+                let enemy_pos = enemy_query
+                    .iter()
+                    .find(|(body, _)| body.index == encounter.target)
+                    .map(|(_, e_trans)| e_trans.translation)
+                    .unwrap_or_else(|| gml_to_bevy(320.0, 136.0));
+
                 let wait_time = if damage > 0 {
                     commands.spawn((
                         SpriteBundle {
@@ -88,6 +106,7 @@ pub fn attack_bar_update(
                 commands.spawn(PendingDamage {
                     timer: Timer::from_seconds(wait_time, TimerMode::Once),
                     damage,
+                    target: encounter.target,
                     target_pos: enemy_pos,
                 });
 
@@ -107,16 +126,22 @@ pub fn attack_bar_update(
 pub fn apply_pending_damage(
     mut commands: Commands,
     time: Res<Time>,
-    mut enemy_state: ResMut<EnemyState>,
+    mut encounter: ResMut<Encounter>,
     mut combat_state: ResMut<CombatState>,
     mut menu_state: ResMut<MenuState>,
     asset_server: Res<AssetServer>,
     _game_fonts: Res<GameFonts>,
     python_runtime: NonSend<PythonRuntime>,
+    current_project: Res<CurrentProject>,
+    mut phase_scripts: ResMut<PhaseScripts>,
     mut query: Query<(Entity, &mut PendingDamage)>,
 ) {
     for (entity, mut pending) in query.iter_mut() {
         if pending.timer.tick(time.delta()).finished() {
+            let Some(enemy_state) = encounter.enemies.get_mut(pending.target) else {
+                commands.entity(entity).despawn();
+                continue;
+            };
             let old_hp = enemy_state.hp;
             enemy_state.hp = (enemy_state.hp - pending.damage).max(0);
             let damage = pending.damage;
@@ -127,10 +152,10 @@ pub fn apply_pending_damage(
             } else {
                 "attackMiss".to_string()
             };
-            if let Some(next_phase) = phase::apply_phase_update(&mut enemy_state, &mut combat_state, &mut menu_state, PROJECT_NAME, "damage", &python_runtime) {
-                if next_phase != combat_state.phase_name {
-                    combat_state.phase_name = next_phase;
-                    combat_state.phase_turn = 0;
+            if let Some(next_phase) = phase::apply_phase_update(enemy_state, &mut combat_state, &mut menu_state, &mut phase_scripts, &current_project.name, "damage", &python_runtime) {
+                if next_phase != enemy_state.phase_name {
+                    enemy_state.phase_name = next_phase;
+                    enemy_state.phase_turn = 0;
                 }
             }
 
@@ -256,13 +281,13 @@ pub fn animate_slice_effect(
 pub fn damage_number_update(
     mut commands: Commands,
     time: Res<Time>,
-    enemy_state: Res<EnemyState>,
+    encounter: Res<Encounter>,
     mut combat_state: ResMut<CombatState>,
     mut menu_state: ResMut<MenuState>,
     mut query: Query<(Entity, &mut Transform, &mut DamageNumber), Without<EnemyBody>>,
     attack_bar_query: Query<Entity, With<AttackBar>>,
     target_box_query: Query<Entity, With<AttackTargetBox>>,
-    mut enemy_query: Query<(Entity, &mut Sprite, &Transform), With<EnemyBody>>,
+    mut enemy_query: Query<(Entity, &EnemyBody, &mut Sprite, &Transform)>,
 ) {
     for (entity, mut transform, mut dmg) in query.iter_mut() {
         dmg.timer.tick(time.delta());
@@ -282,15 +307,21 @@ pub fn damage_number_update(
             for bar_entity in attack_bar_query.iter() { commands.entity(bar_entity).despawn(); }
             for box_entity in target_box_query.iter() { commands.entity(box_entity).despawn(); }
             
-            if enemy_state.hp <= 0 {
-                for (e_entity, sprite, e_transform) in enemy_query.iter_mut() {
+            let target_defeated = encounter.target_enemy().is_some_and(|enemy| !enemy.is_active());
+            if target_defeated {
+                for (e_entity, body, sprite, e_transform) in enemy_query.iter_mut() {
+                    if body.index != encounter.target {
+                        continue;
+                    }
                     commands.entity(e_entity).insert(Vaporizing {
                         scan_line: 0.0,
                         image_handle: sprite.image.clone(),
                         initial_y: e_transform.translation.y,
                     });
                 }
-                combat_state.mn_fight = MainFightState::Menu; 
+            }
+            if encounter.active_indices().is_empty() {
+                combat_state.mn_fight = MainFightState::Victory;
             } else {
                 combat_state.mn_fight = MainFightState::EnemyDialog; 
                 combat_state.bubble_timer.reset(); 
@@ -0,0 +1,249 @@
+use bevy::prelude::*;
+use rustpython_vm::builtins::{PyDictRef, PyListRef};
+use rustpython_vm::{PyObjectRef, VirtualMachine};
+use crate::python_utils::{py_to_f32, read_option_f32, read_option_string};
+use crate::resources::*;
+
+// 足場解析
+pub fn parse_platforms(vm: &VirtualMachine, value: &PyObjectRef, issues: &mut Vec<String>) -> Vec<BoxPlatform> {
+    let mut report = |message: String| {
+        println!("Warning: {}", message);
+        issues.push(message);
+    };
+    let Ok(entries) = value.clone().try_into_value::<Vec<PyObjectRef>>(vm) else {
+        report("platforms must be list".to_string());
+        return Vec::new();
+    };
+    let mut platforms = Vec::new();
+    for entry in entries {
+        let Ok(dict) = entry.try_into_value::<PyDictRef>(vm) else {
+            report("platform must be dict".to_string());
+            continue;
+        };
+        // intも数値として扱う
+        let read = |key: &str| dict.get_item_opt(key, vm).ok().flatten().and_then(|value| py_to_f32(vm, &value));
+        let (Some(x), Some(y), Some(width)) = (read("x"), read("y"), read("width")) else {
+            report("platform needs x, y and width".to_string());
+            continue;
+        };
+        if width <= 0.0 {
+            report("platform width must be > 0".to_string());
+            continue;
+        }
+        let velocity = Vec2::new(read("vx").unwrap_or(0.0), read("vy").unwrap_or(0.0));
+        let vertical = dict.get_item_opt("vertical", vm).ok().flatten().and_then(|value| value.try_into_value::<bool>(vm).ok()).unwrap_or(false);
+        platforms.push(BoxPlatform { position: Vec2::new(x, y), width, velocity, vertical });
+    }
+    platforms
+}
+
+// ボックス命令適用
+fn apply_box_command(vm: &VirtualMachine, command: &PyDictRef, battle_box: &mut BattleBox) {
+    let Some(kind) = read_option_string(vm, command, "type", "box", true) else { return };
+    let duration = read_option_f32(vm, command, "duration", "box", false).unwrap_or(0.0);
+    let easing_name = read_option_string(vm, command, "easing", "box", false).unwrap_or_else(|| "linear".to_string());
+    let easing = BoxEasing::from_name(&easing_name).unwrap_or_else(|| {
+        println!("Warning: box easing unknown {}", easing_name);
+        BoxEasing::Linear
+    });
+    let read = |key: &str| read_option_f32(vm, command, key, "box", true);
+
+    // 補間中なら最終位置を基準にする
+    let base = battle_box.rect_tween.map(|tween| tween.to).unwrap_or(battle_box.target);
+    let next_rect = match kind.as_str() {
+        "moveTo" => {
+            let (Some(x), Some(y)) = (read("x"), read("y")) else { return };
+            Rect::from_center_size(Vec2::new(x, y), base.size())
+        }
+        "resize" => {
+            let (Some(width), Some(height)) = (read("width"), read("height")) else { return };
+            Rect::from_center_size(base.center(), Vec2::new(width.max(0.0), height.max(0.0)))
+        }
+        "setRect" => {
+            let (Some(left), Some(top), Some(right), Some(bottom)) = (read("left"), read("top"), read("right"), read("bottom")) else { return };
+            Rect::new(left, top, right, bottom)
+        }
+        "setPlatforms" => {
+            if let Ok(Some(value)) = command.get_item_opt("platforms", vm) {
+                battle_box.set_platforms(parse_platforms(vm, &value, &mut Vec::new()));
+            }
+            return;
+        }
+        "addPlatform" => {
+            let list = vm.ctx.new_list(vec![command.clone().into()]);
+            battle_box.platforms.extend(parse_platforms(vm, &list.into(), &mut Vec::new()));
+            return;
+        }
+        "rotate" => {
+            let Some(angle) = read("angle") else { return };
+            if duration > 0.0 {
+                battle_box.rotation_tween = Some(BoxTween { from: battle_box.rotation, to: angle, elapsed: 0.0, duration, easing });
+            } else {
+                battle_box.rotation_tween = None;
+                battle_box.rotation = angle;
+            }
+            return;
+        }
+        _ => {
+            println!("Warning: box command unknown {}", kind);
+            return;
+        }
+    };
+
+    if duration > 0.0 {
+        battle_box.rect_tween = Some(BoxTween { from: battle_box.current, to: next_rect, elapsed: 0.0, duration, easing });
+    } else {
+        battle_box.rect_tween = None;
+        battle_box.target = next_rect;
+    }
+}
+
+// ボックス操作スクリプト反映
+pub fn battle_box_script_update(
+    time: Res<Time>,
+    python_runtime: NonSend<PythonRuntime>,
+    combat_state: Res<CombatState>,
+    wave: Res<DanmakuWave>,
+    mut battle_box: ResMut<BattleBox>,
+) {
+    if combat_state.mn_fight != MainFightState::EnemyAttack || !wave.active {
+        return;
+    }
+
+    python_runtime.interpreter.enter(|vm| {
+        let module = match vm.import("box", 0) {
+            Ok(module) => module,
+            Err(err) => {
+                vm.print_exception(err.clone());
+                return;
+            }
+        };
+
+        // 現在の状態を公開
+        if let Ok(state) = module.get_attr("_state", vm) {
+            let current = battle_box.current;
+            let values = [
+                ("left", current.min.x),
+                ("top", current.min.y),
+                ("right", current.max.x),
+                ("bottom", current.max.y),
+                ("angle", battle_box.rotation),
+            ];
+            for (key, value) in values {
+                let _ = state.set_item(key, vm.new_pyobj(value), vm);
+            }
+            let platforms: Vec<PyObjectRef> = battle_box
+                .platforms
+                .iter()
+                .map(|platform| {
+                    let values = [platform.position.x, platform.position.y, platform.width].map(|value| vm.new_pyobj(value));
+                    vm.ctx.new_list(values.to_vec()).into()
+                })
+                .collect();
+            let _ = state.set_item("platforms", vm.ctx.new_list(platforms).into(), vm);
+        }
+
+        let Ok(commands) = module.get_attr("_commands", vm).and_then(|value| value.try_into_value::<PyListRef>(vm)) else { return };
+        let entries = std::mem::take(&mut *commands.borrow_vec_mut());
+        for entry in entries {
+            match entry.try_into_value::<PyDictRef>(vm) {
+                Ok(command) => apply_box_command(vm, &command, &mut battle_box),
+                Err(_) => println!("Warning: box command must be dict"),
+            }
+        }
+    });
+
+    // 足場はボックス内で移動
+    let dt = time.delta_secs();
+    for platform in battle_box.platforms.iter_mut() {
+        platform.position += platform.velocity * dt;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::python_utils::eval_test_expr;
+
+    // boxモジュールに積まれた命令をボックスへ適用
+    fn apply_queued_commands(vm: &VirtualMachine, battle_box: &mut BattleBox) {
+        let module = vm.import("box", 0).unwrap();
+        let commands: Vec<PyDictRef> = module.get_attr("_commands", vm).unwrap().try_into_value(vm).unwrap();
+        for command in &commands {
+            apply_box_command(vm, command, battle_box);
+        }
+    }
+
+    #[test]
+    fn box_commands_accept_ints() {
+        let runtime = PythonRuntime::default();
+        let mut battle_box = BattleBox { target: Rect::new(0.0, 0.0, 100.0, 50.0), ..Default::default() };
+        runtime.interpreter.enter(|vm| {
+            let module = vm.import("box", 0).unwrap();
+            module.get_attr("moveTo", vm).unwrap().call((200, 150), vm).unwrap();
+            module.get_attr("rotate", vm).unwrap().call((90, 2, "easeOut"), vm).unwrap();
+            apply_queued_commands(vm, &mut battle_box);
+        });
+        assert_eq!(battle_box.target, Rect::from_center_size(Vec2::new(200.0, 150.0), Vec2::new(100.0, 50.0)));
+        let tween = battle_box.rotation_tween.unwrap();
+        assert_eq!((tween.to, tween.duration, tween.easing), (90.0, 2.0, BoxEasing::EaseOut));
+    }
+
+    #[test]
+    fn reset_drops_queued_box_commands() {
+        let runtime = PythonRuntime::default();
+        runtime.interpreter.enter(|vm| {
+            let module = vm.import("box", 0).unwrap();
+            module.get_attr("resize", vm).unwrap().call((40, 40), vm).unwrap();
+            module.get_attr("_state", vm).unwrap().set_item("left", vm.new_pyobj(12.0), vm).unwrap();
+        });
+        runtime.reset_script_modules();
+        let mut battle_box = BattleBox::default();
+        runtime.interpreter.enter(|vm| {
+            apply_queued_commands(vm, &mut battle_box);
+            let module = vm.import("box", 0).unwrap();
+            let left: f64 = module.get_attr("_state", vm).unwrap().get_item("left", vm).unwrap().try_into_value(vm).unwrap();
+            assert_eq!(left, 0.0);
+        });
+        assert_eq!(battle_box.target, Rect::default());
+    }
+
+    #[test]
+    fn parse_platforms_accepts_ints_and_vertical() {
+        let (platforms, issues) = eval_test_expr("[{'x': 10, 'y': 20.5, 'width': 40, 'vx': -3}, {'x': 0, 'y': 0, 'width': 30, 'vertical': True}]", |vm, value| {
+            let mut issues = Vec::new();
+            (parse_platforms(vm, &value, &mut issues), issues)
+        });
+        assert!(issues.is_empty());
+        assert_eq!(platforms.len(), 2);
+        assert_eq!((platforms[0].position, platforms[0].width, platforms[0].velocity), (Vec2::new(10.0, 20.5), 40.0, Vec2::new(-3.0, 0.0)));
+        assert!(!platforms[0].vertical);
+        assert!(platforms[1].vertical);
+    }
+
+    #[test]
+    fn parse_platforms_reports_invalid_entries() {
+        let (platforms, issues) = eval_test_expr("[{'x': 1, 'y': 2}, {'x': 1, 'y': 2, 'width': 0}, 5, {'x': 1, 'y': 2, 'width': 'wide'}]", |vm, value| {
+            let mut issues = Vec::new();
+            (parse_platforms(vm, &value, &mut issues), issues)
+        });
+        assert!(platforms.is_empty());
+        assert_eq!(issues.len(), 4);
+    }
+
+    #[test]
+    fn add_platform_command_keeps_revision() {
+        let runtime = PythonRuntime::default();
+        let mut battle_box = BattleBox::default();
+        runtime.interpreter.enter(|vm| {
+            let module = vm.import("box", 0).unwrap();
+            module.get_attr("setPlatforms", vm).unwrap().call((vm.ctx.new_list(vec![]),), vm).unwrap();
+            module.get_attr("addPlatform", vm).unwrap().call((10, 20, 30), vm).unwrap();
+            apply_queued_commands(vm, &mut battle_box);
+        });
+        // 置き換えのみ番号を無効化し、追加では既存の番号を保つ
+        assert_eq!(battle_box.platform_revision, 1);
+        assert_eq!(battle_box.platforms.len(), 1);
+        assert_eq!(battle_box.platforms[0].position, Vec2::new(10.0, 20.0));
+    }
+}
@@ -1,7 +1,11 @@
 use bevy::prelude::*;
-use evalexpr::{Context, ContextWithMutableVariables, Value};
+use evalexpr::{Context, ContextWithMutableVariables, HashMapContext, IterateVariablesContext, Value};
+use rustpython_vm::{PyObjectRef, VirtualMachine};
 use crate::components::*;
+use crate::project::ProjectAssets;
 use crate::resources::*;
+use crate::constants::SOUL_HITBOX_RADIUS;
+use crate::systems::combat::{calculate_damage, hitbox_hits_soul, Combatant, DamageKind};
 
 fn value_to_f64(value: &Value) -> Option<f64> {
     match value {
@@ -21,20 +25,44 @@ fn value_to_bool(value: &Value) -> Option<bool> {
     }
 }
 
+// 弾の色解析
+pub fn parse_bullet_color(vm: &VirtualMachine, value: &PyObjectRef, issues: &mut Vec<String>) -> BulletColor {
+    let mut report = |message: String| {
+        println!("Warning: {}", message);
+        issues.push(message);
+    };
+    let Ok(name) = value.clone().try_into_value::<String>(vm) else {
+        report("bullet color must be str".to_string());
+        return BulletColor::White;
+    };
+    BulletColor::from_name(&name).unwrap_or_else(|| {
+        report(format!("bullet color unknown {}", name));
+        BulletColor::White
+    })
+}
+
+// 弾の色変更
+fn apply_bullet_color(bullet: &mut Bullet, sprite: &mut Sprite, color: BulletColor) {
+    if bullet.color != color {
+        bullet.color = color;
+        sprite.color = color.tint();
+    }
+}
+
 // 弾幕更新
 pub fn leapfrog_bullet_update(
     mut commands: Commands,
     time: Res<Time>,
-    asset_server: Res<AssetServer>,
+    project_assets: ProjectAssets,
     python_runtime: NonSend<PythonRuntime>,
-    mut python_query: Query<(Entity, &mut Transform, &PythonBullet, &mut Sprite), (Without<ExpressionBullet>, Without<LeapFrogBullet>)>,
+    mut python_query: Query<(Entity, &mut Transform, &PythonBullet, &mut Bullet, &mut Sprite), (Without<ExpressionBullet>, Without<LeapFrogBullet>)>,
     mut rust_query: Query<(&mut Transform, &mut LeapFrogBullet, &mut Sprite), (Without<ExpressionBullet>, Without<PythonBullet>)>,
-    mut expr_query: Query<(Entity, &mut Transform, &mut ExpressionBullet, &mut Sprite), (Without<LeapFrogBullet>, Without<PythonBullet>)>,
+    mut expr_query: Query<(Entity, &mut Transform, &mut ExpressionBullet, &mut Bullet, &mut Sprite), (Without<LeapFrogBullet>, Without<PythonBullet>)>,
     _scripts: Res<DanmakuScripts>,
 ) {
     let dt = time.delta_secs();
 
-    for (entity, mut transform, mut bullet, mut sprite) in expr_query.iter_mut() {
+    for (entity, mut transform, mut bullet, mut common, mut sprite) in expr_query.iter_mut() {
         let (context, update_exprs, delete_expr, texture_expr, last_texture) = {
             let ExpressionBullet { context, update_exprs, delete_expr, texture_expr, last_texture, .. } = &mut *bullet;
             (context, update_exprs, delete_expr, texture_expr, last_texture)
@@ -67,6 +95,17 @@ pub fn leapfrog_bullet_update(
                 transform.translation.y = y as f32;
             }
         }
+        if let Some(value) = context.get_value("rotation") {
+            if let Some(rotation) = value_to_f64(value) {
+                transform.rotation = Quat::from_rotation_z((rotation as f32).to_radians());
+            }
+        }
+        // 色は式で切替可能
+        if let Some(Value::String(name)) = context.get_value("color") {
+            if let Some(color) = BulletColor::from_name(name) {
+                apply_bullet_color(&mut common, &mut sprite, color);
+            }
+        }
 
         let mut next_texture: Option<String> = None;
         if let Some(expr) = texture_expr {
@@ -80,7 +119,7 @@ pub fn leapfrog_bullet_update(
         }
         if let Some(texture) = next_texture {
             if last_texture.as_deref() != Some(texture.as_str()) {
-                sprite.image = asset_server.load(&texture);
+                sprite.image = project_assets.load(&texture);
                 *last_texture = Some(texture);
             }
         }
@@ -111,7 +150,7 @@ pub fn leapfrog_bullet_update(
                     bullet.velocity.x = bullet.jump_speed * rad.cos();
                     bullet.velocity.y = bullet.jump_speed * rad.sin();
                     if !bullet.jump_texture.is_empty() {
-                        sprite.image = asset_server.load(&bullet.jump_texture);
+                        sprite.image = project_assets.load(&bullet.jump_texture);
                     }
                 }
             }
@@ -126,7 +165,7 @@ pub fn leapfrog_bullet_update(
     let has_python = python_query.iter().next().is_some();
     if has_python {
         python_runtime.interpreter.enter(|vm| {
-            for (entity, mut transform, bullet, mut sprite) in python_query.iter_mut() {
+            for (entity, mut transform, bullet, mut common, mut sprite) in python_query.iter_mut() {
                 let bullet_obj = bullet.bullet_data.clone();
 
                 let sys_update = match bullet_obj.get_attr("sysUpdate", vm) {
@@ -171,12 +210,20 @@ pub fn leapfrog_bullet_update(
                     transform.translation.x = x;
                     transform.translation.y = y;
                 }
+                // 回転は任意
+                if let Some(rotation) = bullet_obj.get_attr("rotation", vm).ok().and_then(|value| value.try_into_value::<f32>(vm).ok()) {
+                    transform.rotation = Quat::from_rotation_z(rotation.to_radians());
+                }
+                // 色も任意
+                if let Some(color) = bullet_obj.get_attr("color", vm).ok().and_then(|value| value.try_into_value::<String>(vm).ok()).and_then(|name| BulletColor::from_name(&name)) {
+                    apply_bullet_color(&mut common, &mut sprite, color);
+                }
 
                 match bullet_obj.get_attr("texture", vm) {
                     Ok(texture_val) => match texture_val.try_into_value::<Option<String>>(vm) {
                         Ok(path) => {
                             if let Some(path) = path {
-                                sprite.image = asset_server.load(path);
+                                sprite.image = project_assets.load(&path);
                             }
                         }
                         Err(err) => {
@@ -208,176 +255,147 @@ pub fn leapfrog_bullet_update(
     }
 }
 
+// 式変数のPython辞書化
+fn expr_vars_to_py(vm: &VirtualMachine, context: &HashMapContext) -> PyObjectRef {
+    let dict = vm.ctx.new_dict();
+    for (name, value) in context.iter_variables() {
+        let value = match value {
+            Value::Float(val) => vm.new_pyobj(val),
+            Value::Int(val) => vm.new_pyobj(val),
+            Value::Boolean(val) => vm.new_pyobj(val),
+            Value::String(val) => vm.new_pyobj(val),
+            _ => continue,
+        };
+        let _ = dict.set_item(name.as_str(), value, vm);
+    }
+    dict.into()
+}
+
 // 被弾判定
 pub fn soul_collision_detection(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut player_state: ResMut<PlayerState>,
-    mut combat_state: ResMut<CombatState>,
-    mut soul_query: Query<(Entity, &Transform), With<Soul>>,
-    python_bullet_query: Query<(&Transform, &PythonBullet)>,
-    leapfrog_bullet_query: Query<(&Transform, &LeapFrogBullet)>,
-    expr_bullet_query: Query<(&Transform, &ExpressionBullet)>,
-    mut visibility_param_set: ParamSet<(
-        Query<&mut Visibility, (With<Sprite>, Without<Soul>, Without<EditorWindow>)>,
-        Query<&mut Visibility, (With<Text2d>, Without<Soul>, Without<EditorWindow>)>,
-    )>,
+    player_state: Res<PlayerState>,
+    combat_state: Res<CombatState>,
+    encounter: Res<Encounter>,
+    item_dictionary: Res<ItemDictionary>,
+    damage_model: Res<DamageModel>,
+    python_runtime: NonSend<PythonRuntime>,
+    images: Res<Assets<Image>>,
+    soul_query: Query<(&Transform, Option<&SoulMotion>), With<Soul>>,
+    mut bullet_query: Query<(Entity, &Transform, &mut Bullet, Option<&Hitbox>, &Sprite, Option<&ExpressionBullet>)>,
+    mut hit_writer: EventWriter<SoulHitEvent>,
+    mut heal_writer: EventWriter<SoulHealEvent>,
 ) {
-    if player_state.invincibility_timer > 0.0 {
+    if combat_state.mn_fight == MainFightState::PlayerDefeated {
         return;
     }
+    let Ok((soul_tf, motion)) = soul_query.get_single() else { return };
+    let soul_pos = soul_tf.translation.truncate();
+    let moving = motion.is_some_and(|motion| motion.moving);
+    let invincible = player_state.invincibility_timer > 0.0;
 
-    if let Ok((soul_entity, soul_tf)) = soul_query.get_single_mut() {
-        let soul_radius = 6.0;
-        let bullet_radius = 10.0;
+    let default_hitbox = Hitbox::default();
+    let mut damage_hit: Option<Bullet> = None;
+    let mut collisions: Vec<(PyObjectRef, Option<HashMapContext>)> = Vec::new();
+    for (entity, bullet_tf, mut bullet, hitbox, sprite, expression) in bullet_query.iter_mut() {
+        let touching = hitbox_hits_soul(hitbox.unwrap_or(&default_hitbox), bullet_tf, sprite, &images, soul_pos, SOUL_HITBOX_RADIUS);
+        // 回復とコールバックは接触開始時のみ
+        let entered = touching && !bullet.touching;
+        bullet.touching = touching;
+        if !touching {
+            continue;
+        }
 
-        for (bullet_tf, bullet) in python_bullet_query.iter() {
-            let distance = soul_tf.translation.distance(bullet_tf.translation);
-            if distance < (soul_radius + bullet_radius) {
-                player_state.hp -= bullet.damage as f32;
-                player_state.invincibility_timer = player_state.invincibility_duration;
-                if player_state.hp <= 0.0 { 
-                    player_state.hp = 0.0; 
-                    combat_state.mn_fight = MainFightState::PlayerDefeated;
-                    for mut visibility in visibility_param_set.p0().iter_mut() {
-                        *visibility = Visibility::Hidden;
-                    }
-                    for mut visibility in visibility_param_set.p1().iter_mut() {
-                        *visibility = Visibility::Hidden;
-                    }
-                    commands.entity(soul_entity).despawn();
-                    commands.spawn((
-                        SpriteBundle {
-                            sprite: Sprite {
-                                color: Color::BLACK,
-                                custom_size: Some(Vec2::new(10000.0, 10000.0)), 
-                                ..default()
-                            },
-                            transform: Transform::from_translation(Vec3::new(0.0, 0.0, 500.0)),
-                            ..default()
-                        },
-                        Cleanup,
-                    ));
-                    commands.spawn((
-                        SpriteBundle {
-                            sprite: Sprite { 
-                                image: asset_server.load("texture/heart/spr_heart_0.png"), 
-                                color: Color::WHITE, 
-                                custom_size: Some(Vec2::new(16.0, 16.0)), 
-                                ..default() 
-                            },
-                            transform: Transform::from_translation(Vec3::new(soul_tf.translation.x, soul_tf.translation.y, 600.0)),
-                            ..default()
-                        },
-                        HeartDefeated {
-                            timer: Timer::from_seconds(1.0, TimerMode::Once), 
-                            state: HeartDefeatedState::InitialDelay,
-                            original_pos: soul_tf.translation,
-                        },
-                        Cleanup,
-                    ));
-                    return;
-                }
+        if let Some(callback) = &bullet.on_collide {
+            if !entered {
+                continue;
             }
-        }
-        for (bullet_tf, bullet) in leapfrog_bullet_query.iter() {
-            let distance = soul_tf.translation.distance(bullet_tf.translation);
-            if distance < (soul_radius + bullet_radius) {
-                player_state.hp -= bullet.damage as f32;
-                player_state.invincibility_timer = player_state.invincibility_duration;
-                if player_state.hp <= 0.0 { 
-                    player_state.hp = 0.0; 
-                    combat_state.mn_fight = MainFightState::PlayerDefeated;
-                    for mut visibility in visibility_param_set.p0().iter_mut() {
-                        *visibility = Visibility::Hidden;
-                    }
-                    for mut visibility in visibility_param_set.p1().iter_mut() {
-                        *visibility = Visibility::Hidden;
-                    }
-                    commands.entity(soul_entity).despawn();
-                    commands.spawn((
-                        SpriteBundle {
-                            sprite: Sprite {
-                                color: Color::BLACK,
-                                custom_size: Some(Vec2::new(10000.0, 10000.0)), 
-                                ..default()
-                            },
-                            transform: Transform::from_translation(Vec3::new(0.0, 0.0, 500.0)),
-                            ..default()
-                        },
-                        Cleanup,
-                    ));
-                    commands.spawn((
-                        SpriteBundle {
-                            sprite: Sprite { 
-                                image: asset_server.load("texture/heart/spr_heart_0.png"), 
-                                color: Color::WHITE, 
-                                custom_size: Some(Vec2::new(16.0, 16.0)), 
-                                ..default() 
-                            },
-                            transform: Transform::from_translation(Vec3::new(soul_tf.translation.x, soul_tf.translation.y, 600.0)),
-                            ..default()
-                        },
-                        HeartDefeated {
-                            timer: Timer::from_seconds(1.0, TimerMode::Once), 
-                            state: HeartDefeatedState::InitialDelay,
-                            original_pos: soul_tf.translation,
-                        },
-                        Cleanup,
-                    ));
-                    return;
-                }
+            collisions.push((callback.clone(), expression.map(|expression| expression.context.clone())));
+        } else if bullet.color == BulletColor::Green {
+            if !entered {
+                continue;
             }
+            heal_writer.send(SoulHealEvent { amount: bullet.damage });
+        } else if !bullet.color.hurts(moving) {
+            // 青弾・橙弾は条件を満たさなければすり抜ける
+            continue;
+        } else if !invincible && damage_hit.is_none() {
+            damage_hit = Some(bullet.clone());
         }
-        for (bullet_tf, bullet) in expr_bullet_query.iter() {
-            let distance = soul_tf.translation.distance(bullet_tf.translation);
-            if distance < (soul_radius + bullet_radius) {
-                player_state.hp -= bullet.damage as f32;
-                player_state.invincibility_timer = player_state.invincibility_duration;
-                if player_state.hp <= 0.0 { 
-                    player_state.hp = 0.0; 
-                    combat_state.mn_fight = MainFightState::PlayerDefeated;
-                    for mut visibility in visibility_param_set.p0().iter_mut() {
-                        *visibility = Visibility::Hidden;
-                    }
-                    for mut visibility in visibility_param_set.p1().iter_mut() {
-                        *visibility = Visibility::Hidden;
-                    }
-                    commands.entity(soul_entity).despawn();
-                    commands.spawn((
-                        SpriteBundle {
-                            sprite: Sprite {
-                                color: Color::BLACK,
-                                custom_size: Some(Vec2::new(10000.0, 10000.0)), 
-                                ..default()
-                            },
-                            transform: Transform::from_translation(Vec3::new(0.0, 0.0, 500.0)),
-                            ..default()
-                        },
-                        Cleanup,
-                    ));
-                    commands.spawn((
-                        SpriteBundle {
-                            sprite: Sprite { 
-                                image: asset_server.load("texture/heart/spr_heart_0.png"), 
-                                color: Color::WHITE, 
-                                custom_size: Some(Vec2::new(16.0, 16.0)), 
-                                ..default() 
-                            },
-                            transform: Transform::from_translation(Vec3::new(soul_tf.translation.x, soul_tf.translation.y, 600.0)),
-                            ..default()
-                        },
-                        HeartDefeated {
-                            timer: Timer::from_seconds(1.0, TimerMode::Once), 
-                            state: HeartDefeatedState::InitialDelay,
-                            original_pos: soul_tf.translation,
-                        },
-                        Cleanup,
-                    ));
-                    return;
+        // 無敵中でも接触した使い捨て弾は消す
+        if bullet.destroy_on_hit {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    // 取得物はダメージの代わりにスクリプトへ通知
+    if !collisions.is_empty() {
+        python_runtime.interpreter.enter(|vm| {
+            for (callback, context) in collisions {
+                let result = match context {
+                    Some(context) => callback.call((expr_vars_to_py(vm, &context),), vm),
+                    None => callback.call((), vm),
+                };
+                if let Err(err) = result {
+                    vm.print_exception(err.clone());
+                    println!("Warning: bullet onCollide {:?}", err);
                 }
             }
+        });
+    }
+
+    let Some(bullet) = damage_hit else { return };
+
+    // 攻撃中の敵から被ダメージ計算
+    let damage = match encounter.enemies.get(combat_state.attacking_enemy).map(Combatant::from_enemy) {
+        Some(attacker) => calculate_damage(
+            &damage_model,
+            &python_runtime,
+            DamageKind::Bullet,
+            &attacker,
+            &Combatant::from_player(&player_state, &item_dictionary).with_bonus(combat_state.attack_bonus, combat_state.defense_bonus),
+            bullet.damage as f32,
+        ),
+        None => bullet.damage,
+    };
+    hit_writer.send(SoulHitEvent { damage });
+}
+
+// 被弾ダメージ適用
+pub fn soul_hit_damage(
+    mut hit_reader: EventReader<SoulHitEvent>,
+    mut player_state: ResMut<PlayerState>,
+    combat_state: Res<CombatState>,
+) {
+    for hit in hit_reader.read() {
+        if combat_state.mn_fight == MainFightState::PlayerDefeated {
+            continue;
+        }
+        player_state.hp = (player_state.hp - hit.damage as f32).max(0.0);
+    }
+}
+
+// 被弾後の無敵開始
+pub fn soul_hit_invincibility(
+    mut hit_reader: EventReader<SoulHitEvent>,
+    mut player_state: ResMut<PlayerState>,
+) {
+    if hit_reader.read().next().is_some() {
+        player_state.invincibility_timer = player_state.invincibility_duration;
+    }
+}
+
+// 回復弾適用
+pub fn soul_heal_apply(
+    mut heal_reader: EventReader<SoulHealEvent>,
+    mut player_state: ResMut<PlayerState>,
+    combat_state: Res<CombatState>,
+) {
+    for heal in heal_reader.read() {
+        if combat_state.mn_fight == MainFightState::PlayerDefeated {
+            continue;
         }
+        player_state.hp = (player_state.hp + heal.amount.max(0) as f32).min(player_state.max_hp);
     }
 }
 
@@ -407,3 +425,29 @@ pub fn invincibility_update(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::python_utils::eval_test_expr;
+
+    #[test]
+    fn parse_bullet_color_names() {
+        let parse = |source: &str| eval_test_expr(source, |vm, value| {
+            let mut issues = Vec::new();
+            (parse_bullet_color(vm, &value, &mut issues), issues.len())
+        });
+        assert_eq!(parse("'blue'"), (BulletColor::Blue, 0));
+        assert_eq!(parse("'cyan'"), (BulletColor::Blue, 0));
+        assert_eq!(parse("'orange'"), (BulletColor::Orange, 0));
+        assert_eq!(parse("'purple'"), (BulletColor::White, 1));
+        assert_eq!(parse("1"), (BulletColor::White, 1));
+    }
+
+    #[test]
+    fn bullet_color_hurts_by_movement() {
+        assert!(BulletColor::White.hurts(true) && BulletColor::White.hurts(false));
+        assert!(BulletColor::Blue.hurts(true) && !BulletColor::Blue.hurts(false));
+        assert!(!BulletColor::Orange.hurts(true) && BulletColor::Orange.hurts(false));
+    }
+}
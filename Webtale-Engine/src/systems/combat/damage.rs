@@ -0,0 +1,231 @@
+use evalexpr::{build_operator_tree, ContextWithMutableVariables, Value};
+use rand::Rng;
+use rustpython_vm::compiler::Mode;
+use rustpython_vm::import::import_codeobj;
+use rustpython_vm::{PyObjectRef, VirtualMachine};
+use crate::python_scripts;
+use crate::resources::*;
+use crate::systems::combat::build_expr_context;
+
+// ダメージ種別
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DamageKind {
+    PlayerAttack,
+    Bullet,
+}
+
+// 戦闘者ステータス
+#[derive(Clone, Debug)]
+pub struct Combatant {
+    pub name: String,
+    pub is_player: bool,
+    pub hp: f32,
+    pub max_hp: f32,
+    pub attack: f32,
+    pub defense: f32,
+}
+
+impl Combatant {
+    // プレイヤー(装備込み)
+    pub fn from_player(player_state: &PlayerState, item_dictionary: &ItemDictionary) -> Self {
+        let (item_attack, item_defense) = player_state
+            .equipped_items
+            .iter()
+            .filter_map(|item| item_dictionary.0.get(item))
+            .fold((0, 0), |(attack, defense), info| (attack + info.attack, defense + info.defense));
+        Self {
+            name: player_state.name.clone(),
+            is_player: true,
+            hp: player_state.hp,
+            max_hp: player_state.max_hp,
+            attack: player_state.attack + item_attack as f32,
+            defense: player_state.defense + item_defense as f32,
+        }
+    }
+
+    // 戦闘中補正
+    pub fn with_bonus(mut self, attack: f32, defense: f32) -> Self {
+        self.attack += attack;
+        self.defense += defense;
+        self
+    }
+
+    // 敵
+    pub fn from_enemy(enemy_state: &EnemyState) -> Self {
+        Self {
+            name: enemy_state.name.clone(),
+            is_player: false,
+            hp: enemy_state.hp as f32,
+            max_hp: enemy_state.max_hp as f32,
+            attack: enemy_state.atk as f32,
+            defense: enemy_state.def as f32,
+        }
+    }
+
+    // Python辞書化
+    fn to_py(&self, vm: &VirtualMachine) -> PyObjectRef {
+        let dict = vm.ctx.new_dict();
+        let _ = dict.set_item("name", vm.new_pyobj(self.name.clone()), vm);
+        let _ = dict.set_item("isPlayer", vm.new_pyobj(self.is_player), vm);
+        let _ = dict.set_item("hp", vm.new_pyobj(self.hp), vm);
+        let _ = dict.set_item("maxHp", vm.new_pyobj(self.max_hp), vm);
+        let _ = dict.set_item("attack", vm.new_pyobj(self.attack), vm);
+        let _ = dict.set_item("defense", vm.new_pyobj(self.defense), vm);
+        dict.into()
+    }
+}
+
+// Undertale準拠の既定式
+pub fn default_damage(kind: DamageKind, attacker: &Combatant, defender: &Combatant, base: f32) -> i32 {
+    if base <= 0.0 {
+        return 0;
+    }
+    match kind {
+        // (AT - DF + 0..2) × 攻撃バー倍率
+        DamageKind::PlayerAttack => {
+            let spread = rand::thread_rng().gen_range(0.0..2.0);
+            ((attacker.attack - defender.defense + spread) * base).round().max(0.0) as i32
+        }
+        // 弾のダメージ - floor(DF / 5)、最低1
+        DamageKind::Bullet => (base - (defender.defense / 5.0).floor()).round().max(1.0) as i32,
+    }
+}
+
+// ダメージ計算
+pub fn calculate_damage(
+    damage_model: &DamageModel,
+    python_runtime: &PythonRuntime,
+    kind: DamageKind,
+    attacker: &Combatant,
+    defender: &Combatant,
+    base: f32,
+) -> i32 {
+    if let Some(hook) = &damage_model.hook {
+        let result = python_runtime.interpreter.enter(|vm| {
+            let args = (attacker.to_py(vm), defender.to_py(vm), vm.new_pyobj(base));
+            match hook.call(args, vm) {
+                // intの戻り値も受け付ける
+                Ok(value) => match value.clone().try_into_value::<f64>(vm).or_else(|_| value.try_into_value::<i64>(vm).map(|damage| damage as f64)) {
+                    Ok(damage) => Some(damage.round() as i32),
+                    Err(err) => {
+                        vm.print_exception(err.clone());
+                        println!("Warning: damageFormula result {:?}", err);
+                        None
+                    }
+                },
+                Err(err) => {
+                    vm.print_exception(err.clone());
+                    println!("Warning: damageFormula call {:?}", err);
+                    None
+                }
+            }
+        });
+        if let Some(damage) = result {
+            return damage.max(0);
+        }
+    } else if let Some(formula) = &damage_model.formula {
+        let mut context = build_expr_context();
+        let variables = [
+            ("base", base),
+            ("attackerHp", attacker.hp),
+            ("attackerMaxHp", attacker.max_hp),
+            ("attackerAttack", attacker.attack),
+            ("attackerDefense", attacker.defense),
+            ("defenderHp", defender.hp),
+            ("defenderMaxHp", defender.max_hp),
+            ("defenderAttack", defender.attack),
+            ("defenderDefense", defender.defense),
+        ];
+        for (name, value) in variables {
+            let _ = context.set_value(name.to_string(), Value::Float(value as f64));
+        }
+        let _ = context.set_value("isBullet".to_string(), Value::Boolean(kind == DamageKind::Bullet));
+        match formula.eval_with_context(&context) {
+            Ok(Value::Float(damage)) => return (damage.round() as i32).max(0),
+            Ok(Value::Int(damage)) => return (damage as i32).max(0),
+            Ok(other) => println!("Warning: damage formula result {:?}", other),
+            Err(err) => println!("Warning: damage formula {:?}", err),
+        }
+    }
+    default_damage(kind, attacker, defender, base)
+}
+
+// ダメージ計算式読込
+pub fn load_damage_model(python_runtime: &PythonRuntime, project_name: &str, manifest: &ProjectManifest) -> DamageModel {
+    let mut damage_model = DamageModel::default();
+
+    if let Some(formula) = &manifest.damage_formula {
+        match build_operator_tree(formula) {
+            Ok(node) => damage_model.formula = Some(node),
+            Err(err) => println!("Warning: damage formula parse {:?}", err),
+        }
+    }
+
+    let Some(script_path) = &manifest.damage_script else { return damage_model };
+    let Some(script) = python_scripts::get_project_script(project_name, script_path) else {
+        println!("Warning: Could not load projects/{}/{}", project_name, script_path);
+        return damage_model;
+    };
+    damage_model.hook = python_runtime.interpreter.enter(|vm| {
+        let code_obj = match vm.compile(&script, Mode::Exec, script_path.clone()) {
+            Ok(code_obj) => code_obj,
+            Err(err) => {
+                println!("Warning: python compile {} {:?}", script_path, err);
+                return None;
+            }
+        };
+        let module = match import_codeobj(vm, "damage", code_obj, true) {
+            Ok(module) => module,
+            Err(err) => {
+                vm.print_exception(err.clone());
+                return None;
+            }
+        };
+        match module.get_attr("damageFormula", vm) {
+            Ok(func) => Some(func),
+            Err(err) => {
+                vm.print_exception(err.clone());
+                println!("Warning: {} missing damageFormula", script_path);
+                None
+            }
+        }
+    });
+    damage_model
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn combatant(attack: f32, defense: f32) -> Combatant {
+        Combatant { name: String::new(), is_player: false, hp: 20.0, max_hp: 20.0, attack, defense }
+    }
+
+    #[test]
+    fn default_bullet_damage_uses_defense() {
+        assert_eq!(default_damage(DamageKind::Bullet, &combatant(0.0, 0.0), &combatant(0.0, 9.0), 5.0), 4);
+        assert_eq!(default_damage(DamageKind::Bullet, &combatant(0.0, 0.0), &combatant(0.0, 50.0), 5.0), 1);
+        assert_eq!(default_damage(DamageKind::Bullet, &combatant(0.0, 0.0), &combatant(0.0, 0.0), 0.0), 0);
+    }
+
+    #[test]
+    fn damage_formula_evaluates_variables() {
+        let damage_model = DamageModel { formula: build_operator_tree("base * 2 - defenderDefense").ok(), ..Default::default() };
+        let damage = calculate_damage(&damage_model, &PythonRuntime::default(), DamageKind::Bullet, &combatant(0.0, 0.0), &combatant(0.0, 1.5), 3.0);
+        assert_eq!(damage, 5);
+    }
+
+    #[test]
+    fn damage_hook_accepts_int_result() {
+        let python_runtime = PythonRuntime::default();
+        let hook = python_runtime.interpreter.enter(|vm| {
+            let code = vm.compile("lambda attacker, defender, base: int(attacker['attack']) - 3", Mode::Eval, "<test>".to_string()).unwrap();
+            vm.run_code_obj(code, vm.new_scope_with_builtins()).unwrap()
+        });
+        let damage_model = DamageModel { hook: Some(hook), ..Default::default() };
+        let damage = calculate_damage(&damage_model, &python_runtime, DamageKind::PlayerAttack, &combatant(10.0, 0.0), &combatant(0.0, 0.0), 1.0);
+        assert_eq!(damage, 7);
+        let damage = calculate_damage(&damage_model, &python_runtime, DamageKind::PlayerAttack, &combatant(1.0, 0.0), &combatant(0.0, 0.0), 1.0);
+        assert_eq!(damage, 0);
+    }
+}
@@ -1,6 +1,8 @@
 use bevy::prelude::*;
 use rand::Rng;
 use crate::components::*;
+use crate::project::ProjectAssets;
+use crate::resources::*;
 
 // 蒸発演出
 pub fn vaporize_enemy_system(
@@ -94,6 +96,50 @@ pub fn vaporize_enemy_system(
     }
 }
 
+// 見逃しフェード
+pub fn spare_fade_update(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Sprite, &mut SpareFade)>,
+) {
+    for (entity, mut sprite, mut fade) in query.iter_mut() {
+        fade.timer.tick(time.delta());
+        sprite.color.set_alpha(0.5 * (1.0 - fade.timer.fraction()));
+
+        if fade.timer.finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+// 逃走演出
+pub fn soul_flee_update(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut combat_state: ResMut<CombatState>,
+    mut battle_finished: EventWriter<BattleFinished>,
+    mut query: Query<(Entity, &mut Transform, &mut Visibility, &mut FleeingSoul)>,
+) {
+    for (entity, mut transform, mut visibility, mut flee) in query.iter_mut() {
+        flee.timer.tick(time.delta());
+
+        // 左へ跳ねながら走り去る
+        transform.translation.x -= 180.0 * time.delta_secs();
+        transform.translation.y += (flee.timer.elapsed_secs() * 30.0).sin() * 40.0 * time.delta_secs();
+
+        if flee.timer.finished() {
+            *visibility = Visibility::Hidden;
+            commands.entity(entity).remove::<FleeingSoul>();
+            if let Some(result) = combat_state.battle_result.clone() {
+                if !combat_state.battle_finished {
+                    combat_state.battle_finished = true;
+                    battle_finished.send(BattleFinished { result });
+                }
+            }
+        }
+    }
+}
+
 // 粒子更新
 pub fn dust_particle_update(
     mut commands: Commands,
@@ -113,3 +159,48 @@ pub fn dust_particle_update(
         }
     }
 }
+
+// 被弾演出
+pub fn soul_hit_feedback(
+    mut commands: Commands,
+    project_assets: ProjectAssets,
+    manifest: Res<ProjectManifest>,
+    mut hit_reader: EventReader<SoulHitEvent>,
+    mut screen_shake: ResMut<ScreenShake>,
+) {
+    for hit in hit_reader.read() {
+        if hit.damage <= 0 {
+            continue;
+        }
+        if let Some(hurt_sound) = &manifest.hurt_sound {
+            commands.spawn((
+                AudioPlayer::<AudioSource>::new(project_assets.load(hurt_sound)),
+                PlaybackSettings::DESPAWN,
+                Cleanup,
+            ));
+        }
+        screen_shake.timer = 0.2;
+        screen_shake.intensity = 3.0;
+    }
+}
+
+// 画面揺れ
+pub fn screen_shake_update(
+    time: Res<Time>,
+    mut screen_shake: ResMut<ScreenShake>,
+    mut camera_query: Query<&mut Transform, With<MainCamera>>,
+) {
+    let Ok(mut transform) = camera_query.get_single_mut() else { return };
+    if screen_shake.timer <= 0.0 {
+        if transform.translation.x != 0.0 || transform.translation.y != 0.0 {
+            transform.translation.x = 0.0;
+            transform.translation.y = 0.0;
+        }
+        return;
+    }
+    screen_shake.timer -= time.delta_secs();
+    let mut rng = rand::thread_rng();
+    let intensity = screen_shake.intensity;
+    transform.translation.x = rng.gen_range(-intensity..=intensity).round();
+    transform.translation.y = rng.gen_range(-intensity..=intensity).round();
+}
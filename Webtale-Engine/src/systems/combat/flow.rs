@@ -2,7 +2,7 @@ use bevy::prelude::*;
 use bevy::sprite::Anchor;
 use rand::Rng;
 use bevy_egui::EguiContexts;
-use evalexpr::{build_operator_tree, Context, ContextWithMutableFunctions, ContextWithMutableVariables, EvalexprError, Function, HashMapContext, Value};
+use evalexpr::{build_operator_tree, ContextWithMutableFunctions, ContextWithMutableVariables, EvalexprError, Function, HashMapContext, Value};
 use rustpython_vm::builtins::{PyDictRef, PyListRef};
 use rustpython_vm::compiler::Mode;
 use rustpython_vm::import::import_codeobj;
@@ -10,20 +10,23 @@ use rustpython_vm::PyObjectRef;
 use rustpython_vm::VirtualMachine;
 use crate::components::*;
 use crate::constants::*;
+use crate::project::ProjectAssets;
 use crate::python_scripts;
 use crate::resources::*;
+use crate::systems::combat::parse_wave_init;
 use crate::systems::phase;
 
 // 戦闘フロー
 pub fn battle_flow_control(
     mut commands: Commands,
-    mut enemy_state: ResMut<EnemyState>,
+    mut encounter: ResMut<Encounter>,
     mut combat_state: ResMut<CombatState>,
     mut menu_state: ResMut<MenuState>,
-    asset_server: Res<AssetServer>,
+    project_assets: ProjectAssets,
     game_fonts: Res<GameFonts>,
     python_runtime: NonSend<PythonRuntime>,
-    _time: Res<Time>,
+    current_project: Res<CurrentProject>,
+    mut phase_scripts: ResMut<PhaseScripts>,
     input: Res<ButtonInput<KeyCode>>, 
     mut box_res: ResMut<BattleBox>,
     bubbles: Query<Entity, With<SpeechBubble>>,
@@ -41,66 +44,66 @@ pub fn battle_flow_control(
     if combat_state.mn_fight == MainFightState::EnemyDialog {
         if bubbles.is_empty() {
             combat_state.turn_count += 1;
-            combat_state.phase_turn += 1;
-            if let Some(next_phase) = phase::apply_phase_update(&mut enemy_state, &mut combat_state, &mut menu_state, PROJECT_NAME, "turn", &python_runtime) {
-                if next_phase != combat_state.phase_name {
-                    combat_state.phase_name = next_phase;
-                    combat_state.phase_turn = 1;
-                    let _ = phase::apply_phase_update(&mut enemy_state, &mut combat_state, &mut menu_state, PROJECT_NAME, "turn", &python_runtime);
+            for enemy_state in encounter.enemies.iter_mut().filter(|enemy| enemy.is_active()) {
+                enemy_state.phase_turn += 1;
+                if let Some(next_phase) = phase::apply_phase_update(enemy_state, &mut combat_state, &mut menu_state, &mut phase_scripts, &current_project.name, "turn", &python_runtime) {
+                    if next_phase != enemy_state.phase_name {
+                        enemy_state.phase_name = next_phase;
+                        enemy_state.phase_turn = 1;
+                        let _ = phase::apply_phase_update(enemy_state, &mut combat_state, &mut menu_state, &mut phase_scripts, &current_project.name, "turn", &python_runtime);
+                    }
                 }
             }
 
             box_res.target = Rect::new(32.0, 250.0, 602.0, 385.0);
-            let bubble_pos = enemy_state.bubble_pos_override.unwrap_or(Vec2::new(320.0 + 40.0, 160.0 - 95.0));
-            let bubble_x = bubble_pos.x; 
-            let bubble_y = bubble_pos.y; 
-            let bubble_texture = if enemy_state.bubble_texture.is_empty() {
-                "texture/blcon/spr_blconsm.png".to_string()
-            } else {
-                enemy_state.bubble_texture.clone()
-            };
-            commands.spawn((
-                SpriteBundle {
-                    sprite: Sprite { 
-                        image: asset_server.load(bubble_texture),
-                        color: Color::WHITE, 
-                        custom_size: Some(Vec2::new(100.0, 80.0)), 
-                        anchor: Anchor::TopLeft, 
-                        ..default() 
+            for enemy_state in encounter.enemies.iter_mut().filter(|enemy| enemy.is_active()) {
+                // 敵位置基準の吹き出し
+                let bubble_pos = enemy_state.bubble_pos_override.unwrap_or(Vec2::new(enemy_state.base_x + 40.0, enemy_state.base_y - 95.0));
+                let bubble_x = bubble_pos.x; 
+                let bubble_y = bubble_pos.y; 
+                let bubble_texture = if enemy_state.bubble_texture.is_empty() {
+                    "texture/blcon/spr_blconsm.png".to_string()
+                } else {
+                    enemy_state.bubble_texture.clone()
+                };
+                commands.spawn((
+                    SpriteBundle {
+                        sprite: Sprite { 
+                            image: project_assets.load(&bubble_texture),
+                            color: Color::WHITE, 
+                            custom_size: Some(Vec2::new(100.0, 80.0)), 
+                            anchor: Anchor::TopLeft, 
+                            ..default() 
+                        },
+                        transform: Transform::from_translation(gml_to_bevy(bubble_x, bubble_y) + Vec3::new(0.0, 0.0, Z_BUBBLE)),
+                        ..default()
                     },
-                    transform: Transform::from_translation(gml_to_bevy(bubble_x, bubble_y) + Vec3::new(0.0, 0.0, Z_BUBBLE)),
-                    ..default()
-                },
-                SpeechBubble,
-                Cleanup,
-            ));
-            let msg = if let Some(message) = enemy_state.bubble_message_override.take() {
-                message
-            } else if enemy_state.bubble_messages.is_empty() {
-                println!("Warning: enemy bubble messages missing");
-                "...".to_string()
-            } else {
-                let idx = rand::thread_rng().gen_range(0..enemy_state.bubble_messages.len());
-                enemy_state.bubble_messages[idx].clone()
-            };
-            commands.spawn((
-                Text2d::new(""),
-                TextFont { font: game_fonts.dialog.clone(), font_size: 24.0 * TEXT_SCALE, ..default() },
-                TextColor(Color::BLACK),
-                Anchor::TopLeft,
-                Transform::from_translation(gml_to_bevy(bubble_x + 15.0, bubble_y + 15.0) + Vec3::new(0.0, 0.0, Z_BUBBLE_TEXT)),
-                Typewriter { full_text: msg, visible_chars: 0, timer: Timer::from_seconds(0.05, TimerMode::Repeating), finished: false },
-                SpeechBubble, 
-                Cleanup,
-            ));
-        }
-        
-        let mut is_finished = false;
-        if let Ok(writer) = bubble_text_query.get_single() {
-            if writer.finished {
-                is_finished = true;
+                    SpeechBubble,
+                    Cleanup,
+                ));
+                let msg = if let Some(message) = enemy_state.bubble_message_override.take() {
+                    message
+                } else if enemy_state.bubble_messages.is_empty() {
+                    println!("Warning: enemy bubble messages missing");
+                    "...".to_string()
+                } else {
+                    let idx = rand::thread_rng().gen_range(0..enemy_state.bubble_messages.len());
+                    enemy_state.bubble_messages[idx].clone()
+                };
+                commands.spawn((
+                    Text2d::new(""),
+                    TextFont { font: game_fonts.dialog.clone(), font_size: 24.0 * TEXT_SCALE, ..default() },
+                    TextColor(Color::BLACK),
+                    Anchor::TopLeft,
+                    Transform::from_translation(gml_to_bevy(bubble_x + 15.0, bubble_y + 15.0) + Vec3::new(0.0, 0.0, Z_BUBBLE_TEXT)),
+                    Typewriter { full_text: msg, visible_chars: 0, timer: Timer::from_seconds(0.05, TimerMode::Repeating), finished: false },
+                    SpeechBubble, 
+                    Cleanup,
+                ));
             }
         }
+        
+        let is_finished = !bubble_text_query.is_empty() && bubble_text_query.iter().all(|writer| writer.finished);
 
         if is_finished && input.just_pressed(KeyCode::KeyZ) {
             for entity in bubbles.iter() { commands.entity(entity).despawn_recursive(); }
@@ -108,17 +111,83 @@ pub fn battle_flow_control(
             combat_state.mn_fight = MainFightState::EnemyAttack; 
             combat_state.turn_timer = -1.0; 
             
-            box_res.target = Rect::new(217.0, 125.0, 417.0, 385.0);
+            let [left, top, right, bottom] = ATTACK_BOX;
+            box_res.target = Rect::new(left, top, right, bottom);
             
-            let box_center_x = (217.0 + 417.0) / 2.0;
-            let box_center_y = (125.0 + 385.0) / 2.0;
+            let box_center = box_res.target.center();
             if let Ok(mut t) = soul_query.get_single_mut() {
-                t.translation = gml_to_bevy(box_center_x, box_center_y) + Vec3::new(0.0, 0.0, Z_SOUL);
+                t.translation = gml_to_bevy(box_center.x, box_center.y) + Vec3::new(0.0, 0.0, Z_SOUL);
             }
         }
     }
 }
 
+// 勝利報酬
+pub fn victory_reward_update(
+    mut commands: Commands,
+    game_fonts: Res<GameFonts>,
+    lv_table: Res<LvTable>,
+    encounter: Res<Encounter>,
+    mut combat_state: ResMut<CombatState>,
+    mut player_state: ResMut<PlayerState>,
+    typewriter_query: Query<Entity, With<MainDialogText>>,
+) {
+    if combat_state.mn_fight != MainFightState::Victory || combat_state.battle_result.is_some() {
+        return;
+    }
+    let result = encounter.battle_result();
+    player_state.gold += result.gold;
+    let leveled = player_state.add_exp(result.exp, &lv_table);
+    let mut text = format!("* YOU WON!\n* You earned {} EXP and {} gold.", result.exp, result.gold);
+    if leveled {
+        text.push_str("\n* Your LOVE increased.");
+    }
+    for entity in typewriter_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    commands.spawn((
+        Text2d::new(""),
+        TextFont { font: game_fonts.dialog.clone(), font_size: 32.0 * TEXT_SCALE, ..default() },
+        TextColor(Color::WHITE),
+        Anchor::TopLeft,
+        Transform::from_translation(gml_to_bevy(52.0, 270.0) + Vec3::new(0.0, 0.0, Z_TEXT)),
+        Typewriter { full_text: text, visible_chars: 0, timer: Timer::from_seconds(0.03, TimerMode::Repeating), finished: false },
+        MainDialogText,
+        Cleanup,
+    ));
+    combat_state.battle_result = Some(result);
+}
+
+// 戦闘終了通知
+pub fn battle_end_update(
+    input: Res<ButtonInput<KeyCode>>,
+    mut combat_state: ResMut<CombatState>,
+    typewriter_query: Query<&Typewriter, With<MainDialogText>>,
+    mut battle_finished: EventWriter<BattleFinished>,
+) {
+    if combat_state.mn_fight != MainFightState::Victory || combat_state.battle_finished {
+        return;
+    }
+    let text_finished = !typewriter_query.is_empty() && typewriter_query.iter().all(|writer| writer.finished);
+    if !text_finished || !input.just_pressed(KeyCode::KeyZ) {
+        return;
+    }
+    if let Some(result) = combat_state.battle_result.clone() {
+        combat_state.battle_finished = true;
+        battle_finished.send(BattleFinished { result });
+    }
+}
+
+// 戦闘結果出力
+pub fn report_battle_result(mut events: EventReader<BattleFinished>) {
+    for event in events.read() {
+        println!(
+            "Battle finished: {:?} (spared: {:?}, killed: {:?}, exp: {}, gold: {})",
+            event.result.outcome, event.result.spared, event.result.killed, event.result.exp, event.result.gold
+        );
+    }
+}
+
 fn parse_number(value: &Value) -> Result<f64, EvalexprError> {
     match value {
         Value::Int(val) => Ok(*val as f64),
@@ -137,7 +206,7 @@ fn parse_bool(value: &Value) -> Result<bool, EvalexprError> {
     }
 }
 
-fn build_expr_context() -> HashMapContext {
+pub fn build_expr_context() -> HashMapContext {
     let mut context = HashMapContext::new();
     let _ = context.set_value("pi".to_string(), Value::Float(std::f64::consts::PI));
     let _ = context.set_value("dt".to_string(), Value::Float(0.0));
@@ -166,6 +235,22 @@ fn build_expr_context() -> HashMapContext {
         let x = parse_number(&args[0])?;
         Ok(Value::Float(x.tan()))
     }));
+    let _ = context.set_function("floor".to_string(), Function::new(|args| {
+        let args = match args {
+            Value::Tuple(values) => values.as_slice(),
+            other => std::slice::from_ref(other),
+        };
+        let x = parse_number(&args[0])?;
+        Ok(Value::Float(x.floor()))
+    }));
+    let _ = context.set_function("round".to_string(), Function::new(|args| {
+        let args = match args {
+            Value::Tuple(values) => values.as_slice(),
+            other => std::slice::from_ref(other),
+        };
+        let x = parse_number(&args[0])?;
+        Ok(Value::Float(x.round()))
+    }));
     let _ = context.set_function("min".to_string(), Function::new(|args| {
         let args = match args {
             Value::Tuple(values) => values.as_slice(),
@@ -215,38 +300,23 @@ fn build_expr_context() -> HashMapContext {
     context
 }
 
-fn py_value_to_evalexpr(vm: &VirtualMachine, value: &PyObjectRef) -> Option<Value> {
-    if let Ok(val) = value.clone().try_into_value::<bool>(vm) {
-        return Some(Value::Boolean(val));
-    }
-    if let Ok(val) = value.clone().try_into_value::<i64>(vm) {
-        return Some(Value::Float(val as f64));
-    }
-    if let Ok(val) = value.clone().try_into_value::<f64>(vm) {
-        return Some(Value::Float(val));
-    }
-    if let Ok(val) = value.clone().try_into_value::<String>(vm) {
-        return Some(Value::String(val));
-    }
-    None
-}
-
-fn parse_rust_sim_spec(vm: &VirtualMachine, rust_sim: &PyDictRef) -> Option<RustSimSpec> {
+// rustSim解析
+pub fn parse_rust_sim_spec(vm: &VirtualMachine, rust_sim: &PyDictRef, issues: &mut Vec<String>) -> Option<RustSimSpec> {
     let mut update_exprs = Vec::new();
     match rust_sim.get_item_opt("update", vm) {
         Ok(Some(value)) => {
-            let parse_update_pair = |entry: &PyObjectRef| -> Option<(String, String)> {
+            let parse_update_pair = |entry: &PyObjectRef, issues: &mut Vec<String>| -> Option<(String, String)> {
                 if let Ok(list) = entry.clone().try_into_value::<PyListRef>(vm) {
                     let items = list.borrow_vec();
                     if items.len() != 2 {
-                        println!("Warning: rustSim update list len invalid");
+                        report_rust_sim_issue(issues, "rustSim update list len invalid".to_string());
                         return None;
                     }
                     let target: String = match items[0].clone().try_into_value(vm) {
                         Ok(result) => result,
                         Err(err) => {
                             vm.print_exception(err.clone());
-                            println!("Warning: rustSim update list key {:?}", err);
+                            report_rust_sim_issue(issues, format!("rustSim update list key {:?}", err));
                             return None;
                         }
                     };
@@ -254,23 +324,23 @@ fn parse_rust_sim_spec(vm: &VirtualMachine, rust_sim: &PyDictRef) -> Option<Rust
                         Ok(result) => result,
                         Err(err) => {
                             vm.print_exception(err.clone());
-                            println!("Warning: rustSim update list value {:?}", err);
+                            report_rust_sim_issue(issues, format!("rustSim update list value {:?}", err));
                             return None;
                         }
                     };
                     return Some((target, expr_text));
                 }
-                println!("Warning: rustSim update entry invalid");
+                report_rust_sim_issue(issues, "rustSim update entry invalid".to_string());
                 None
             };
 
             if let Ok(list) = value.clone().try_into_value::<PyListRef>(vm) {
                 let entries = list.borrow_vec();
                 for entry in entries.iter() {
-                    if let Some((target, expr_text)) = parse_update_pair(entry) {
+                    if let Some((target, expr_text)) = parse_update_pair(entry, issues) {
                         match build_operator_tree(&expr_text) {
                             Ok(expr) => update_exprs.push(ExprAssignment { target, expr }),
-                            Err(err) => println!("Warning: rustSim update parse {:?}", err),
+                            Err(err) => report_rust_sim_issue(issues, format!("rustSim update parse {:?}", err)),
                         }
                     }
                 }
@@ -279,7 +349,7 @@ fn parse_rust_sim_spec(vm: &VirtualMachine, rust_sim: &PyDictRef) -> Option<Rust
                     Ok(dict) => dict,
                     Err(err) => {
                         vm.print_exception(err.clone());
-                        println!("Warning: rustSim update invalid");
+                        report_rust_sim_issue(issues, "rustSim update invalid".to_string());
                         return None;
                     }
                 };
@@ -288,7 +358,7 @@ fn parse_rust_sim_spec(vm: &VirtualMachine, rust_sim: &PyDictRef) -> Option<Rust
                         Ok(result) => result,
                         Err(err) => {
                             vm.print_exception(err.clone());
-                            println!("Warning: rustSim update key {:?}", err);
+                            report_rust_sim_issue(issues, format!("rustSim update key {:?}", err));
                             continue;
                         }
                     };
@@ -296,13 +366,13 @@ fn parse_rust_sim_spec(vm: &VirtualMachine, rust_sim: &PyDictRef) -> Option<Rust
                         Ok(result) => result,
                         Err(err) => {
                             vm.print_exception(err.clone());
-                            println!("Warning: rustSim update value {:?}", err);
+                            report_rust_sim_issue(issues, format!("rustSim update value {:?}", err));
                             continue;
                         }
                     };
                     match build_operator_tree(&expr_text) {
                         Ok(expr) => update_exprs.push(ExprAssignment { target, expr }),
-                        Err(err) => println!("Warning: rustSim update parse {:?}", err),
+                        Err(err) => report_rust_sim_issue(issues, format!("rustSim update parse {:?}", err)),
                     }
                 }
             }
@@ -310,7 +380,7 @@ fn parse_rust_sim_spec(vm: &VirtualMachine, rust_sim: &PyDictRef) -> Option<Rust
         Ok(None) => {}
         Err(err) => {
             vm.print_exception(err.clone());
-            println!("Warning: rustSim update {:?}", err);
+            report_rust_sim_issue(issues, format!("rustSim update {:?}", err));
             return None;
         }
     }
@@ -320,13 +390,13 @@ fn parse_rust_sim_spec(vm: &VirtualMachine, rust_sim: &PyDictRef) -> Option<Rust
             Ok(text) => match build_operator_tree(&text) {
                 Ok(expr) => Some(expr),
                 Err(err) => {
-                    println!("Warning: rustSim delete parse {:?}", err);
+                    report_rust_sim_issue(issues, format!("rustSim delete parse {:?}", err));
                     None
                 }
             },
             Err(err) => {
                 vm.print_exception(err.clone());
-                println!("Warning: rustSim delete invalid");
+                report_rust_sim_issue(issues, "rustSim delete invalid".to_string());
                 None
             }
         },
@@ -338,13 +408,13 @@ fn parse_rust_sim_spec(vm: &VirtualMachine, rust_sim: &PyDictRef) -> Option<Rust
             Ok(text) => match build_operator_tree(&text) {
                 Ok(expr) => Some(expr),
                 Err(err) => {
-                    println!("Warning: rustSim texture parse {:?}", err);
+                    report_rust_sim_issue(issues, format!("rustSim texture parse {:?}", err));
                     None
                 }
             },
             Err(err) => {
                 vm.print_exception(err.clone());
-                println!("Warning: rustSim texture invalid");
+                report_rust_sim_issue(issues, "rustSim texture invalid".to_string());
                 None
             }
         },
@@ -354,40 +424,57 @@ fn parse_rust_sim_spec(vm: &VirtualMachine, rust_sim: &PyDictRef) -> Option<Rust
     Some(RustSimSpec { update_exprs, delete_expr, texture_expr })
 }
 
+// rustSim警告記録
+fn report_rust_sim_issue(issues: &mut Vec<String>, message: String) {
+    println!("Warning: {}", message);
+    issues.push(message);
+}
+
 // 弾幕ターン管理
 pub fn combat_turn_manager(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
     time: Res<Time>,
-    enemy_state: Res<EnemyState>,
+    encounter: Res<Encounter>,
     mut combat_state: ResMut<CombatState>,
     mut menu_state: ResMut<MenuState>,
     mut battle_box: ResMut<BattleBox>,
     python_runtime: NonSend<PythonRuntime>,
-    bullet_query: Query<Entity, Or<(With<PythonBullet>, With<ExpressionBullet>, With<LeapFrogBullet>)>>,
+    current_project: Res<CurrentProject>,
+    bullet_query: Query<Entity, With<Bullet>>,
     mut scripts: ResMut<DanmakuScripts>,
+    mut wave: ResMut<DanmakuWave>,
+    mut soul_query: Query<(&mut Transform, &mut SoulMotion), With<Soul>>,
 ) {
     if combat_state.mn_fight == MainFightState::EnemyAttack {
         if combat_state.turn_timer < 0.0 {
-            combat_state.turn_timer = 5.0; 
+            combat_state.turn_timer = ATTACK_DURATION;
             
-            let attack_patterns = &enemy_state.attacks;
+            // 生存敵の攻撃候補
+            let attack_patterns: Vec<(usize, String)> = encounter
+                .enemies
+                .iter()
+                .enumerate()
+                .filter(|(_, enemy)| enemy.is_active())
+                .flat_map(|(index, enemy)| enemy.attacks.iter().map(move |attack| (index, attack.clone())))
+                .collect();
             let script_name = if !attack_patterns.is_empty() {
                 let mut rng = rand::thread_rng();
                 let idx = rng.gen_range(0..attack_patterns.len());
-                attack_patterns[idx].clone()
+                combat_state.attacking_enemy = attack_patterns[idx].0;
+                attack_patterns[idx].1.clone()
             } else {
                 println!("Warning: enemyStatus attackPatterns missing");
                 "frogJump".to_string() 
             };
             
+            let project_name = current_project.name.as_str();
             let cached_api = scripts.modules.get("api").cloned();
             let cached_module = scripts.modules.get(&script_name).cloned();
             let script_content = if cached_module.is_none() {
-                match python_scripts::get_danmaku_script(PROJECT_NAME, &script_name) {
+                match python_scripts::get_danmaku_script(project_name, &script_name) {
                     Some(content) => Some(content),
                     None => {
-                        println!("Warning: script missing projects/{}/danmaku/{}.py", PROJECT_NAME, script_name);
+                        println!("Warning: script missing projects/{}/danmaku/{}.py", project_name, script_name);
                         return;
                     }
                 }
@@ -396,10 +483,10 @@ pub fn combat_turn_manager(
             };
 
             let api_content = if cached_api.is_none() {
-                match python_scripts::get_danmaku_api_script(PROJECT_NAME) {
+                match python_scripts::get_danmaku_api_script(project_name) {
                     Some(content) => Some(content),
                     None => {
-                        println!("Warning: script missing projects/{}/danmaku/api.py", PROJECT_NAME);
+                        println!("Warning: script missing projects/{}/danmaku/api.py", project_name);
                         return;
                     }
                 }
@@ -499,25 +586,6 @@ pub fn combat_turn_manager(
                     }
                 };
 
-                let box_data_obj = match init_data.get_item_opt("box", vm) {
-                    Ok(Some(value)) => value,
-                    Ok(None) => {
-                        println!("Warning: danmaku box missing");
-                        return;
-                    }
-                    Err(err) => {
-                        vm.print_exception(err.clone());
-                        return;
-                    }
-                };
-                let _box_data: Vec<f32> = match box_data_obj.try_into_value(vm) {
-                    Ok(value) => value,
-                    Err(err) => {
-                        vm.print_exception(err.clone());
-                        return;
-                    }
-                };
-
                 let texture_path_obj = match init_data.get_item_opt("textureWait", vm) {
                     Ok(Some(value)) => value,
                     Ok(None) => {
@@ -561,7 +629,7 @@ pub fn combat_turn_manager(
                         None => None,
                     };
                     if let Some(dict) = rust_sim_dict {
-                        if let Some(spec) = parse_rust_sim_spec(vm, &dict) {
+                        if let Some(spec) = parse_rust_sim_spec(vm, &dict, &mut Vec::new()) {
                             scripts.rust_specs.insert(script_name.clone(), spec.clone());
                             Some(spec)
                         } else {
@@ -572,165 +640,55 @@ pub fn combat_turn_manager(
                     }
                 };
 
-                let spawn_x = ORIGIN_X + battle_box.current.max.x - 40.0;
-                let spawn_y = ORIGIN_Y - battle_box.current.max.y + 40.0;
-
-                let spawn_func = match module.get_attr("spawn", vm) {
-                    Ok(func) => func,
-                    Err(err) => {
-                        vm.print_exception(err.clone());
-                        return;
-                    }
-                };
-                let spawn_result: PyObjectRef = match vm.invoke(&spawn_func, ()) {
-                    Ok(result) => result,
-                    Err(err) => {
-                        vm.print_exception(err.clone());
-                        return;
-                    }
+                *wave = DanmakuWave {
+                    active: true,
+                    script_name: script_name.clone(),
+                    module: Some(module.clone()),
+                    texture_path,
+                    rust_sim: rust_sim_spec,
+                    ..default()
                 };
+                parse_wave_init(vm, &module, &init_data, &mut wave, &mut Vec::new());
+            });
 
-                if let Some(spec) = rust_sim_spec {
-                    let spawn_dict = match spawn_result.clone().try_into_value::<PyDictRef>(vm) {
-                        Ok(dict) => Some(dict),
-                        Err(_) => {
-                            println!("Warning: rustSim spawn must return dict");
-                            None
-                        }
-                    };
-                    if let Some(spawn_dict) = spawn_dict {
-                        let mut context = build_expr_context();
-                        let mut damage: Option<i32> = None;
-                        let vars_dict = match spawn_dict.get_item_opt("vars", vm) {
-                            Ok(Some(value)) => match value.try_into_value::<PyDictRef>(vm) {
-                                Ok(dict) => Some(dict),
-                                Err(err) => {
-                                    vm.print_exception(err.clone());
-                                    println!("Warning: rustSim vars invalid");
-                                    None
-                                }
-                            },
-                            _ => None,
-                        };
-                        let source_dict = vars_dict.as_ref().unwrap_or(&spawn_dict);
-                        for (key, value) in source_dict {
-                            let key_name: String = match key.try_into_value(vm) {
-                                Ok(result) => result,
-                                Err(err) => {
-                                    vm.print_exception(err.clone());
-                                    continue;
-                                }
-                            };
-                            if let Some(value) = py_value_to_evalexpr(vm, &value) {
-                                let _ = context.set_value(key_name, value);
-                            }
-                        }
-                        if let Ok(Some(value)) = spawn_dict.get_item_opt("damage", vm) {
-                            if let Ok(result) = value.try_into_value::<i32>(vm) {
-                                damage = Some(result);
-                            }
-                        }
-                        if damage.is_none() {
-                            if let Some(vars_dict) = vars_dict.as_ref() {
-                                if let Ok(Some(value)) = vars_dict.get_item_opt("damage", vm) {
-                                    if let Ok(result) = value.try_into_value::<i32>(vm) {
-                                        damage = Some(result);
-                                    }
-                                }
-                            }
-                        }
-                        if context.get_value("x").is_none() {
-                            let _ = context.set_value("x".to_string(), Value::Float(spawn_x as f64));
-                        }
-                        if context.get_value("y").is_none() {
-                            let _ = context.set_value("y".to_string(), Value::Float(spawn_y as f64));
-                        }
-                        let mut initial_texture = texture_path.clone();
-                        if let Some(Value::String(value)) = context.get_value("texture") {
-                            initial_texture = value.clone();
-                        }
-                        if let Ok(Some(value)) = spawn_dict.get_item_opt("texture", vm) {
-                            if let Ok(result) = value.try_into_value::<String>(vm) {
-                                initial_texture = result;
-                            }
-                        }
-                        if context.get_value("texture").is_none() {
-                            let _ = context.set_value("texture".to_string(), Value::String(initial_texture.clone()));
-                        }
-                        let _ = context.set_value("dt".to_string(), Value::Float(0.0));
-                        let _ = context.set_value("t".to_string(), Value::Float(0.0));
-
-                        commands.spawn((
-                            SpriteBundle {
-                                sprite: Sprite { image: asset_server.load(&initial_texture), ..default() },
-                                transform: Transform::from_xyz(spawn_x, spawn_y, 30.0).with_scale(Vec3::splat(1.0)),
-                                ..default()
-                            },
-                            ExpressionBullet {
-                                context,
-                                update_exprs: spec.update_exprs.clone(),
-                                delete_expr: spec.delete_expr.clone(),
-                                texture_expr: spec.texture_expr.clone(),
-                                damage: damage.unwrap_or(0),
-                                last_texture: Some(initial_texture),
-                            },
-                            Cleanup,
-                        ));
-                        return;
-                    }
-                }
-
-                match spawn_result.get_attr("setPos", vm) {
-                    Ok(set_pos) => {
-                        if let Err(err) = vm.invoke(&set_pos, (spawn_x, spawn_y)) {
-                            vm.print_exception(err.clone());
-                        }
-                    }
-                    Err(err) => {
-                        vm.print_exception(err.clone());
+            // 弾幕ごとのボックスと時間
+            if wave.active {
+                combat_state.turn_timer = wave.duration;
+                if let Some(box_rect) = wave.box_rect {
+                    battle_box.target = box_rect;
+                    battle_box.rect_tween = None;
+                    let soul_start = wave.soul_start.unwrap_or(box_rect.center());
+                    if let Ok((mut transform, _)) = soul_query.get_single_mut() {
+                        transform.translation = gml_to_bevy(soul_start.x, soul_start.y) + Vec3::new(0.0, 0.0, Z_SOUL);
                     }
                 }
+                battle_box.set_platforms(wave.platforms.clone());
+            }
 
-                let damage = match spawn_result.get_attr("damage", vm) {
-                    Ok(value) => match value.try_into_value::<i32>(vm) {
-                        Ok(result) => result,
-                        Err(err) => {
-                            vm.print_exception(err.clone());
-                            println!("Warning: bullet damage {:?}", err);
-                            0
-                        }
-                    },
-                    Err(err) => {
-                        vm.print_exception(err.clone());
-                        println!("Warning: bullet damage {:?}", err);
-                        0
-                    }
-                };
-
-                commands.spawn((
-                    SpriteBundle {
-                        sprite: Sprite { image: asset_server.load(texture_path), ..default() },
-                        transform: Transform::from_xyz(spawn_x, spawn_y, 30.0).with_scale(Vec3::splat(1.0)),
-                        ..default()
-                    },
-                    PythonBullet {
-                        script_name: script_name.clone(),
-                        bullet_data: spawn_result.clone(),
-                        damage,
-                    },
-                    Cleanup,
-                ));
-
-            });
+            // ソウルモードは弾幕、なければフェーズの指定
+            let attacker = encounter.enemies.get(combat_state.attacking_enemy);
+            let mode = wave.soul_mode.or(attacker.map(|enemy| enemy.soul_mode)).unwrap_or_default();
+            let gravity = wave.gravity.or(attacker.map(|enemy| enemy.soul_gravity)).unwrap_or_default();
+            if let Ok((_, mut motion)) = soul_query.get_single_mut() {
+                motion.set_mode(mode, gravity);
+            }
         }
 
         combat_state.turn_timer -= time.delta_secs();
 
-        if combat_state.turn_timer <= 0.0 {
+        // 全弾消滅で終了
+        let cleared = wave.active
+            && wave.end_when_clear
+            && wave.spawned > 0
+            && wave.next_entry >= wave.schedule.len()
+            && bullet_query.is_empty();
+
+        if combat_state.turn_timer <= 0.0 || cleared {
             for entity in bullet_query.iter() {
                 commands.entity(entity).despawn();
             }
             
+            wave.active = false;
             combat_state.mn_fight = MainFightState::TurnCleanup;
             combat_state.turn_timer = -1.0;
         }
@@ -738,8 +696,19 @@ pub fn combat_turn_manager(
         combat_state.mn_fight = MainFightState::Menu;
         combat_state.my_fight = MessageFightState::None;
         menu_state.menu_layer = 0;
-        menu_state.dialog_text = enemy_state.dialog_text.clone(); 
+        if let Some(enemy_state) = encounter.enemies.iter().find(|enemy| enemy.is_active() && !enemy.dialog_text.is_empty()) {
+            menu_state.dialog_text = enemy_state.dialog_text.clone();
+        }
         
         battle_box.target = Rect::new(32.0, 250.0, 602.0, 385.0);
+        battle_box.rect_tween = None;
+        battle_box.rotation_tween = None;
+        battle_box.rotation = 0.0;
+        battle_box.set_platforms(Vec::new());
+        if let Ok((_, mut motion)) = soul_query.get_single_mut() {
+            motion.set_mode(SoulMode::Red, GravityDirection::Down);
+        }
+        // 攻撃外で積まれた命令を次の攻撃へ持ち越さない
+        python_runtime.reset_script_modules();
     }
 }
@@ -2,6 +2,66 @@ use bevy::prelude::*;
 use rand::Rng;
 use std::f32::consts::PI;
 use crate::components::*;
+use crate::resources::*;
+
+// 敗北開始
+pub fn soul_defeat_start(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut hit_reader: EventReader<SoulHitEvent>,
+    player_state: Res<PlayerState>,
+    mut combat_state: ResMut<CombatState>,
+    soul_query: Query<(Entity, &Transform), With<Soul>>,
+    mut visibility_param_set: ParamSet<(
+        Query<&mut Visibility, (With<Sprite>, Without<Soul>, Without<EditorWindow>)>,
+        Query<&mut Visibility, (With<Text2d>, Without<Soul>, Without<EditorWindow>)>,
+    )>,
+) {
+    // HPが尽きた被弾でのみ開始
+    if hit_reader.read().count() == 0 || player_state.hp > 0.0 || combat_state.mn_fight == MainFightState::PlayerDefeated {
+        return;
+    }
+    let Ok((soul_entity, soul_tf)) = soul_query.get_single() else { return };
+    combat_state.mn_fight = MainFightState::PlayerDefeated;
+
+    for mut visibility in visibility_param_set.p0().iter_mut() {
+        *visibility = Visibility::Hidden;
+    }
+    for mut visibility in visibility_param_set.p1().iter_mut() {
+        *visibility = Visibility::Hidden;
+    }
+    commands.entity(soul_entity).despawn();
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::BLACK,
+                custom_size: Some(Vec2::new(10000.0, 10000.0)), 
+                ..default()
+            },
+            transform: Transform::from_translation(Vec3::new(0.0, 0.0, 500.0)),
+            ..default()
+        },
+        Cleanup,
+    ));
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite { 
+                image: asset_server.load("texture/heart/spr_heart_0.png"), 
+                color: Color::WHITE, 
+                custom_size: Some(Vec2::new(16.0, 16.0)), 
+                ..default() 
+            },
+            transform: Transform::from_translation(Vec3::new(soul_tf.translation.x, soul_tf.translation.y, 600.0)),
+            ..default()
+        },
+        HeartDefeated {
+            timer: Timer::from_seconds(1.0, TimerMode::Once), 
+            state: HeartDefeatedState::InitialDelay,
+            original_pos: soul_tf.translation,
+        },
+        Cleanup,
+    ));
+}
 
 // ソウル破壊演出
 pub fn heart_defeated_update(
@@ -0,0 +1,156 @@
+use bevy::prelude::*;
+use rustpython_vm::builtins::PyDictRef;
+use rustpython_vm::{PyObjectRef, VirtualMachine};
+use crate::components::*;
+use crate::python_utils::{py_to_f32, read_option_string};
+
+// 当たり判定解析
+pub fn parse_hitbox(vm: &VirtualMachine, value: &PyObjectRef, issues: &mut Vec<String>) -> Option<Hitbox> {
+    let mut report = |message: String| {
+        println!("Warning: {}", message);
+        issues.push(message);
+    };
+    let Ok(dict) = value.clone().try_into_value::<PyDictRef>(vm) else {
+        report("hitbox must be dict".to_string());
+        return None;
+    };
+    let Some(kind) = read_option_string(vm, &dict, "type", "hitbox", false) else {
+        report("hitbox missing type".to_string());
+        return None;
+    };
+    // intも数値として扱う
+    let number = |key: &str| dict.get_item_opt(key, vm).ok().flatten().and_then(|value| py_to_f32(vm, &value));
+    let mut read = |key: &str| match number(key) {
+        Some(value) if value >= 0.0 => Some(value),
+        _ => {
+            report(format!("hitbox {} {} must be number >= 0", kind, key));
+            None
+        }
+    };
+    let shape = match kind.as_str() {
+        "circle" => HitboxShape::Circle { radius: read("radius")? },
+        "rect" => HitboxShape::Rect { width: read("width")?, height: read("height")? },
+        "capsule" => HitboxShape::Capsule { length: read("length")?, radius: read("radius")? },
+        "mask" => HitboxShape::Mask { threshold: number("threshold").unwrap_or(0.5) },
+        _ => {
+            report(format!("hitbox type unknown {}", kind));
+            return None;
+        }
+    };
+    let angle = number("angle").unwrap_or(0.0);
+    let offset_values = dict.get_item_opt("offset", vm).ok().flatten().filter(|value| !vm.is_none(value)).map(|value| {
+        value.try_into_value::<Vec<PyObjectRef>>(vm).ok()
+            .and_then(|items| items.iter().map(|item| py_to_f32(vm, item)).collect::<Option<Vec<f32>>>())
+    });
+    let offset = match offset_values {
+        Some(Some(values)) if values.len() == 2 => Vec2::new(values[0], values[1]),
+        Some(_) => {
+            report("hitbox offset must be [x, y]".to_string());
+            Vec2::ZERO
+        }
+        None => Vec2::ZERO,
+    };
+    Some(Hitbox { shape, angle, offset })
+}
+
+// 魂との接触判定
+pub fn hitbox_hits_soul(hitbox: &Hitbox, bullet_tf: &Transform, sprite: &Sprite, images: &Assets<Image>, soul_pos: Vec2, soul_radius: f32) -> bool {
+    // 魂の位置を弾のローカル座標へ変換
+    let rotation = Rot2::radians(bullet_tf.rotation.to_euler(EulerRot::XYZ).2) * Rot2::degrees(hitbox.angle);
+    let scale = bullet_tf.scale.truncate().abs().max(Vec2::splat(f32::EPSILON));
+    let center = bullet_tf.translation.truncate() + rotation * (hitbox.offset * scale);
+    let local = rotation.inverse() * (soul_pos - center) / scale;
+    let radius = soul_radius / scale.min_element();
+
+    match hitbox.shape {
+        HitboxShape::Circle { radius: bullet_radius } => local.length() < radius + bullet_radius,
+        HitboxShape::Rect { width, height } => {
+            let half = Vec2::new(width, height) / 2.0;
+            local.distance(local.clamp(-half, half)) < radius
+        }
+        HitboxShape::Capsule { length, radius: bullet_radius } => {
+            let closest = Vec2::new(local.x.clamp(-length / 2.0, length / 2.0), 0.0);
+            local.distance(closest) < radius + bullet_radius
+        }
+        HitboxShape::Mask { threshold } => {
+            let Some(image) = images.get(&sprite.image) else {
+                return local.length() < radius + 10.0;
+            };
+            let image_size = image.size_f32();
+            let size = sprite.custom_size.unwrap_or(image_size);
+            if size.x <= 0.0 || size.y <= 0.0 {
+                return false;
+            }
+            // 魂の円内にある不透明ピクセルを探す
+            let pixel_scale = image_size / size;
+            let reach = radius.ceil() as i32;
+            for dy in -reach..=reach {
+                for dx in -reach..=reach {
+                    if (dx * dx + dy * dy) as f32 > radius * radius {
+                        continue;
+                    }
+                    let point = local + Vec2::new(dx as f32, dy as f32);
+                    let u = (point.x + size.x / 2.0) * pixel_scale.x;
+                    let v = (size.y / 2.0 - point.y) * pixel_scale.y;
+                    if u < 0.0 || v < 0.0 || u >= image_size.x || v >= image_size.y {
+                        continue;
+                    }
+                    let u = if sprite.flip_x { image_size.x - 1.0 - u } else { u };
+                    let v = if sprite.flip_y { image_size.y - 1.0 - v } else { v };
+                    if image.get_color_at(u as u32, v as u32).is_ok_and(|color| color.alpha() > threshold) {
+                        return true;
+                    }
+                }
+            }
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::python_utils::eval_test_expr;
+
+    fn parse(source: &str) -> (Option<Hitbox>, Vec<String>) {
+        eval_test_expr(source, |vm, value| {
+            let mut issues = Vec::new();
+            (parse_hitbox(vm, &value, &mut issues), issues)
+        })
+    }
+
+    #[test]
+    fn parse_hitbox_accepts_ints() {
+        let (hitbox, issues) = parse("{'type': 'rect', 'width': 10, 'height': 4.5, 'angle': 90, 'offset': [2, -3]}");
+        let hitbox = hitbox.unwrap();
+        assert!(matches!(hitbox.shape, HitboxShape::Rect { width, height } if width == 10.0 && height == 4.5));
+        assert_eq!(hitbox.angle, 90.0);
+        assert_eq!(hitbox.offset, Vec2::new(2.0, -3.0));
+        assert!(issues.is_empty());
+    }
+
+    #[test]
+    fn parse_hitbox_rejects_invalid_shapes() {
+        assert!(parse("{'type': 'circle', 'radius': -1}").0.is_none());
+        assert!(parse("{'type': 'star'}").0.is_none());
+        assert!(parse("[1, 2]").0.is_none());
+        let (hitbox, issues) = parse("{'type': 'circle', 'radius': 3, 'offset': [1]}");
+        assert_eq!(hitbox.unwrap().offset, Vec2::ZERO);
+        assert_eq!(issues.len(), 1);
+    }
+
+    #[test]
+    fn hitbox_shapes_hit_soul() {
+        let images = Assets::<Image>::default();
+        let sprite = Sprite::default();
+        let bullet_tf = Transform::from_xyz(100.0, 50.0, 0.0);
+        let circle = Hitbox { shape: HitboxShape::Circle { radius: 5.0 }, angle: 0.0, offset: Vec2::ZERO };
+        assert!(hitbox_hits_soul(&circle, &bullet_tf, &sprite, &images, Vec2::new(107.0, 50.0), 3.0));
+        assert!(!hitbox_hits_soul(&circle, &bullet_tf, &sprite, &images, Vec2::new(109.0, 50.0), 3.0));
+
+        // 90度回転した横長の矩形は縦に当たる
+        let rect = Hitbox { shape: HitboxShape::Rect { width: 40.0, height: 4.0 }, angle: 90.0, offset: Vec2::ZERO };
+        assert!(hitbox_hits_soul(&rect, &bullet_tf, &sprite, &images, Vec2::new(100.0, 68.0), 1.0));
+        assert!(!hitbox_hits_soul(&rect, &bullet_tf, &sprite, &images, Vec2::new(118.0, 50.0), 1.0));
+    }
+}
@@ -0,0 +1,201 @@
+use bevy::prelude::*;
+use bevy::ecs::system::SystemParam;
+use bevy::sprite::Anchor;
+use rustpython_vm::builtins::PyDictRef;
+use crate::components::*;
+use crate::constants::*;
+use crate::python_utils::{py_dict_to_vars, read_option_f32, read_option_i32, vars_to_py_dict};
+use crate::resources::*;
+use crate::systems::phase;
+
+// 回復と使用メッセージ
+pub fn apply_item_heal(player_state: &mut PlayerState, item_name: &str, heal_amount: i32, flavor_text: &str) -> String {
+    let old_hp = player_state.hp;
+    player_state.hp = (player_state.hp + heal_amount as f32).min(player_state.max_hp);
+    let recovered = (player_state.hp - old_hp) as i32;
+
+    let mut text = format!("* You ate the {}.", item_name);
+
+    if player_state.hp >= player_state.max_hp {
+        text.push_str("\n* Your HP was maxed out!");
+    } else {
+        if !flavor_text.is_empty() {
+            text.push_str(&format!("\n* {}", flavor_text));
+        }
+        text.push_str(&format!("\n* You recovered {} HP!", recovered));
+    }
+    text
+}
+
+// アイテム使用で変更する戦闘状態
+#[derive(SystemParam)]
+pub(crate) struct ItemUseState<'w> {
+    player_state: ResMut<'w, PlayerState>,
+    encounter: ResMut<'w, Encounter>,
+    combat_state: ResMut<'w, CombatState>,
+    menu_state: ResMut<'w, MenuState>,
+}
+
+// アイテムスクリプト効果
+pub fn item_use_update(
+    mut commands: Commands,
+    game_fonts: Res<GameFonts>,
+    python_runtime: NonSend<PythonRuntime>,
+    item_dictionary: Res<ItemDictionary>,
+    state: ItemUseState,
+    query: Query<(Entity, &PendingItemUse)>,
+) {
+    let ItemUseState { mut player_state, mut encounter, mut combat_state, mut menu_state } = state;
+    for (entity, pending) in query.iter() {
+        commands.entity(entity).despawn();
+        let Some(info) = item_dictionary.0.get(&pending.item_name) else { continue };
+        let Some(callback) = info.use_callback.clone() else { continue };
+
+        // 対象は選択中の敵、いなければ先頭の生存敵
+        let target = if encounter.target_enemy().is_some_and(|enemy| enemy.is_active()) {
+            Some(encounter.target)
+        } else {
+            encounter.active_indices().first().copied()
+        };
+
+        let mut heal_amount = info.heal_amount;
+        let mut custom_text: Option<String> = None;
+        let mut next_phase: Option<String> = None;
+        python_runtime.interpreter.enter(|vm| {
+            // 敵がいなくても消費済みなので効果は実行
+            let mut enemy_state = target.and_then(|index| encounter.enemies.get_mut(index));
+            let phase_name = enemy_state.as_ref().map(|enemy| enemy.phase_name.clone()).unwrap_or_default();
+            let context = match enemy_state.as_deref() {
+                Some(enemy) => phase::build_phase_context(vm, enemy, &combat_state, &phase_name, "item"),
+                None => {
+                    let context = vm.ctx.new_dict();
+                    let _ = context.set_item("turn", vm.new_pyobj(combat_state.turn_count), vm);
+                    let _ = context.set_item("trigger", vm.new_pyobj("item"), vm);
+                    let _ = context.set_item("battleVars", vars_to_py_dict(vm, &combat_state.battle_vars).into(), vm);
+                    context
+                }
+            };
+            let _ = context.set_item("item", vm.new_pyobj(pending.item_name.clone()), vm);
+            let _ = context.set_item("playerHp", vm.new_pyobj(player_state.hp), vm);
+            let _ = context.set_item("playerMaxHp", vm.new_pyobj(player_state.max_hp), vm);
+            let _ = context.set_item("playerLv", vm.new_pyobj(player_state.lv), vm);
+            let _ = context.set_item("playerAttack", vm.new_pyobj(player_state.attack + combat_state.attack_bonus), vm);
+            let _ = context.set_item("playerDefense", vm.new_pyobj(player_state.defense + combat_state.defense_bonus), vm);
+
+            let result = callback.call((context.clone(),), vm);
+            match enemy_state.as_deref_mut() {
+                Some(enemy) => phase::store_phase_vars(vm, &context, enemy, &mut combat_state, &phase_name),
+                None => {
+                    if let Ok(Some(value)) = context.get_item_opt("battleVars", vm) {
+                        match value.try_into_value::<PyDictRef>(vm) {
+                            Ok(dict) => combat_state.battle_vars = py_dict_to_vars(vm, &dict, "battle vars"),
+                            Err(_) => println!("Warning: battleVars must be dict"),
+                        }
+                    }
+                }
+            }
+            let result = match result {
+                Ok(result) => result,
+                Err(err) => {
+                    vm.print_exception(err.clone());
+                    println!("Warning: item {} use {:?}", pending.item_name, err);
+                    return;
+                }
+            };
+            if vm.is_none(&result) {
+                return;
+            }
+            let dict = match result.try_into_value::<PyDictRef>(vm) {
+                Ok(dict) => dict,
+                Err(err) => {
+                    vm.print_exception(err.clone());
+                    println!("Warning: item {} use must return dict", pending.item_name);
+                    return;
+                }
+            };
+
+            if let Some(enemy) = enemy_state {
+                next_phase = phase::apply_phase_state(vm, &dict, enemy, &combat_state, &mut menu_state, "item");
+            }
+            if let Some(heal) = read_option_i32(vm, &dict, "heal", "item", false) {
+                heal_amount += heal;
+            }
+            if let Some(attack) = read_option_f32(vm, &dict, "attack", "item", false) {
+                combat_state.attack_bonus += attack;
+            }
+            if let Some(defense) = read_option_f32(vm, &dict, "defense", "item", false) {
+                combat_state.defense_bonus += defense;
+            }
+            if let Ok(Some(value)) = dict.get_item_opt("healOverTime", vm) {
+                match value.try_into_value::<PyDictRef>(vm) {
+                    Ok(heal_dict) => {
+                        let amount = read_option_i32(vm, &heal_dict, "amount", "item healOverTime", true).unwrap_or(0);
+                        let turns = read_option_i32(vm, &heal_dict, "turns", "item healOverTime", true).unwrap_or(0);
+                        if amount > 0 && turns > 0 {
+                            let last_turn = combat_state.turn_count;
+                            combat_state.heal_over_time.push(HealOverTime { amount, turns, last_turn });
+                        }
+                    }
+                    Err(_) => println!("Warning: item healOverTime must be dict"),
+                }
+            }
+            if let Ok(Some(value)) = dict.get_item_opt("text", vm) {
+                if let Ok(lines) = value.clone().try_into_value::<Vec<String>>(vm) {
+                    custom_text = Some(lines.join("\n"));
+                } else {
+                    match value.try_into_value::<String>(vm) {
+                        Ok(text) => custom_text = Some(text),
+                        Err(_) => println!("Warning: item text must be str or list"),
+                    }
+                }
+            }
+        });
+
+        if let Some(next_phase) = next_phase {
+            if let Some(enemy_state) = target.and_then(|index| encounter.enemies.get_mut(index)) {
+                if next_phase != enemy_state.phase_name {
+                    enemy_state.phase_name = next_phase;
+                    enemy_state.phase_turn = 0;
+                }
+            }
+        }
+
+        let text = match custom_text {
+            Some(text) => {
+                player_state.hp = (player_state.hp + heal_amount as f32).min(player_state.max_hp);
+                text
+            }
+            None => apply_item_heal(&mut player_state, &pending.item_name, heal_amount, &info.text),
+        };
+
+        commands.spawn((
+            Text2d::new(""),
+            TextFont { font: game_fonts.dialog.clone(), font_size: 32.0 * TEXT_SCALE, ..default() },
+            TextColor(Color::WHITE),
+            Anchor::TopLeft,
+            Transform::from_translation(gml_to_bevy(52.0, 270.0) + Vec3::new(0.0, 0.0, Z_TEXT)),
+            Typewriter { full_text: text, visible_chars: 0, timer: Timer::from_seconds(0.03, TimerMode::Repeating), finished: false },
+            MainDialogText,
+            Cleanup,
+        ));
+    }
+}
+
+// 継続回復
+pub fn heal_over_time_update(
+    mut combat_state: ResMut<CombatState>,
+    mut player_state: ResMut<PlayerState>,
+) {
+    let turn_count = combat_state.turn_count;
+    if combat_state.heal_over_time.iter().all(|heal| heal.last_turn >= turn_count) {
+        return;
+    }
+    for heal in combat_state.heal_over_time.iter_mut() {
+        if heal.last_turn < turn_count && player_state.hp > 0.0 {
+            player_state.hp = (player_state.hp + heal.amount as f32).min(player_state.max_hp);
+            heal.turns -= 1;
+            heal.last_turn = turn_count;
+        }
+    }
+    combat_state.heal_over_time.retain(|heal| heal.turns > 0);
+}
@@ -1,13 +1,25 @@
 // 戦闘システム一覧
 pub mod flow;
 pub mod bullet;
+pub mod wave;
+pub mod battle_box;
+pub mod soul;
+pub mod hitbox;
 pub mod attack;
+pub mod damage;
+pub mod item;
 pub mod effects;
 pub mod game_over;
 
 // 再エクスポート
 pub use flow::*;
 pub use bullet::*;
+pub use wave::*;
+pub use battle_box::*;
+pub use soul::*;
+pub use hitbox::*;
 pub use attack::*;
+pub use damage::*;
+pub use item::*;
 pub use effects::*;
 pub use game_over::*;
@@ -0,0 +1,85 @@
+use bevy::prelude::*;
+use rustpython_vm::builtins::{PyDictRef, PyListRef};
+use rustpython_vm::VirtualMachine;
+use crate::components::*;
+use crate::constants::*;
+use crate::python_utils::read_option_string;
+use crate::resources::*;
+
+// 重力方向読込
+fn read_gravity(vm: &VirtualMachine, command: &PyDictRef) -> Option<GravityDirection> {
+    let name = command.get_item_opt("gravity", vm).ok().flatten().and_then(|value| value.try_into_value::<String>(vm).ok())?;
+    let gravity = GravityDirection::from_name(&name);
+    if gravity.is_none() {
+        println!("Warning: soul gravity unknown {}", name);
+    }
+    gravity
+}
+
+// ソウル命令適用
+fn apply_soul_command(vm: &VirtualMachine, command: &PyDictRef, motion: &mut SoulMotion) {
+    let Some(kind) = read_option_string(vm, command, "type", "soul", true) else { return };
+    match kind.as_str() {
+        "setMode" => {
+            let Some(name) = read_option_string(vm, command, "mode", "soul", true) else { return };
+            let Some(mode) = SoulMode::from_name(&name) else {
+                println!("Warning: soul mode unknown {}", name);
+                return;
+            };
+            let gravity = read_gravity(vm, command).unwrap_or(motion.gravity);
+            motion.set_mode(mode, gravity);
+        }
+        "setGravity" => {
+            if let Some(gravity) = read_gravity(vm, command) {
+                motion.set_gravity(gravity);
+            }
+        }
+        // 指定方向へ叩きつけ(青ソウルになる)
+        "slam" => {
+            let gravity = read_gravity(vm, command).unwrap_or(motion.gravity);
+            motion.set_mode(SoulMode::Blue, gravity);
+            motion.fall_speed = SOUL_SLAM_SPEED;
+            motion.slamming = true;
+        }
+        _ => println!("Warning: soul command unknown {}", kind),
+    }
+}
+
+// ソウル操作スクリプト反映
+pub fn soul_script_update(
+    python_runtime: NonSend<PythonRuntime>,
+    combat_state: Res<CombatState>,
+    wave: Res<DanmakuWave>,
+    mut soul_query: Query<&mut SoulMotion, With<Soul>>,
+) {
+    if combat_state.mn_fight != MainFightState::EnemyAttack || !wave.active {
+        return;
+    }
+    let Ok(mut motion) = soul_query.get_single_mut() else { return };
+
+    python_runtime.interpreter.enter(|vm| {
+        let module = match vm.import("soul", 0) {
+            Ok(module) => module,
+            Err(err) => {
+                vm.print_exception(err.clone());
+                return;
+            }
+        };
+
+        let Ok(commands) = module.get_attr("_commands", vm).and_then(|value| value.try_into_value::<PyListRef>(vm)) else { return };
+        let entries = std::mem::take(&mut *commands.borrow_vec_mut());
+        for entry in entries {
+            match entry.try_into_value::<PyDictRef>(vm) {
+                Ok(command) => apply_soul_command(vm, &command, &mut motion),
+                Err(_) => println!("Warning: soul command must be dict"),
+            }
+        }
+
+        // 現在の状態を公開
+        if let Ok(state) = module.get_attr("_state", vm) {
+            let _ = state.set_item("mode", vm.new_pyobj(motion.mode.name()), vm);
+            let _ = state.set_item("gravity", vm.new_pyobj(motion.gravity.name()), vm);
+            let _ = state.set_item("grounded", vm.new_pyobj(motion.grounded), vm);
+        }
+    });
+}
@@ -0,0 +1,525 @@
+use bevy::prelude::*;
+use evalexpr::{Context, ContextWithMutableVariables, Value};
+use rustpython_vm::builtins::{PyDictRef, PyListRef};
+use rustpython_vm::{PyObjectRef, VirtualMachine};
+use crate::components::*;
+use crate::constants::*;
+use crate::project::ProjectAssets;
+use crate::python_utils::{callable_arg_count, py_to_f32, read_option_i32, read_option_string};
+use crate::resources::*;
+use crate::systems::combat::{build_expr_context, parse_bullet_color, parse_hitbox, parse_platforms};
+
+fn py_value_to_evalexpr(vm: &VirtualMachine, value: &PyObjectRef) -> Option<Value> {
+    if let Ok(val) = value.clone().try_into_value::<bool>(vm) {
+        return Some(Value::Boolean(val));
+    }
+    if let Ok(val) = value.clone().try_into_value::<i64>(vm) {
+        return Some(Value::Float(val as f64));
+    }
+    if let Ok(val) = value.clone().try_into_value::<f64>(vm) {
+        return Some(Value::Float(val));
+    }
+    if let Ok(val) = value.clone().try_into_value::<String>(vm) {
+        return Some(Value::String(val));
+    }
+    None
+}
+
+// ウェーブ警告記録
+fn report_wave_issue(issues: &mut Vec<String>, message: String) {
+    println!("Warning: {}", message);
+    issues.push(message);
+}
+
+// 数値設定読込
+fn read_wave_number(vm: &VirtualMachine, init_data: &PyDictRef, key: &str, issues: &mut Vec<String>) -> Option<f32> {
+    match init_data.get_item_opt(key, vm) {
+        Ok(Some(value)) => match py_to_f32(vm, &value) {
+            Some(result) => Some(result),
+            None => {
+                report_wave_issue(issues, format!("danmaku {} must be number", key));
+                None
+            }
+        },
+        _ => None,
+    }
+}
+
+// 数値リスト読込
+fn read_wave_numbers(vm: &VirtualMachine, init_data: &PyDictRef, key: &str, len: usize, issues: &mut Vec<String>) -> Option<Vec<f32>> {
+    match init_data.get_item_opt(key, vm) {
+        Ok(Some(value)) => match value.try_into_value::<Vec<PyObjectRef>>(vm).ok().and_then(|items| items.iter().map(|item| py_to_f32(vm, item)).collect::<Option<Vec<f32>>>()) {
+            Some(values) if values.len() == len => Some(values),
+            _ => {
+                report_wave_issue(issues, format!("danmaku {} must be list of {} numbers", key, len));
+                None
+            }
+        },
+        _ => None,
+    }
+}
+
+// ウェーブ設定解析
+pub fn parse_wave_init(vm: &VirtualMachine, module: &PyObjectRef, init_data: &PyDictRef, wave: &mut DanmakuWave, issues: &mut Vec<String>) {
+    // ボックスと魂の初期位置(GML座標)
+    let [left, top, right, bottom] = ATTACK_BOX;
+    wave.box_rect = Some(Rect::new(left, top, right, bottom));
+    if let Some(values) = read_wave_numbers(vm, init_data, "box", 4, issues) {
+        if values[0] < values[2] && values[1] < values[3] {
+            wave.box_rect = Some(Rect::new(values[0], values[1], values[2], values[3]));
+        } else {
+            report_wave_issue(issues, "danmaku box must be [left, top, right, bottom]".to_string());
+        }
+    }
+    wave.soul_start = read_wave_numbers(vm, init_data, "soul", 2, issues).map(|values| Vec2::new(values[0], values[1]));
+
+    // ソウルモードと足場
+    if let Some(name) = read_option_string(vm, init_data, "soulMode", "danmaku", false) {
+        wave.soul_mode = SoulMode::from_name(&name);
+        if wave.soul_mode.is_none() {
+            report_wave_issue(issues, format!("danmaku soulMode unknown {}", name));
+        }
+    }
+    if let Some(name) = read_option_string(vm, init_data, "gravity", "danmaku", false) {
+        wave.gravity = GravityDirection::from_name(&name);
+        if wave.gravity.is_none() {
+            report_wave_issue(issues, format!("danmaku gravity unknown {}", name));
+        }
+    }
+    if let Ok(Some(value)) = init_data.get_item_opt("platforms", vm) {
+        wave.platforms = parse_platforms(vm, &value, issues);
+    }
+
+    wave.duration = ATTACK_DURATION;
+    if let Some(duration) = read_wave_number(vm, init_data, "duration", issues) {
+        if duration > 0.0 {
+            wave.duration = duration;
+        } else {
+            report_wave_issue(issues, "danmaku duration must be > 0".to_string());
+        }
+    }
+    if let Ok(Some(value)) = init_data.get_item_opt("endWhenClear", vm) {
+        match value.try_into_value::<bool>(vm) {
+            Ok(end_when_clear) => wave.end_when_clear = end_when_clear,
+            Err(_) => report_wave_issue(issues, "danmaku endWhenClear must be bool".to_string()),
+        }
+    }
+
+    wave.count = 1;
+    if let Ok(Some(value)) = init_data.get_item_opt("count", vm) {
+        match value.try_into_value::<i32>(vm) {
+            Ok(count) if count >= 1 => wave.count = count,
+            _ => report_wave_issue(issues, "danmaku count must be int >= 1".to_string()),
+        }
+    }
+    wave.angle = read_wave_number(vm, init_data, "angle", issues).unwrap_or(0.0);
+    wave.spread = read_wave_number(vm, init_data, "spread", issues).unwrap_or(360.0);
+
+    // 未指定なら開始時に1回
+    wave.schedule = vec![WaveEntry { time: 0.0, args: None }];
+    if let Ok(Some(value)) = init_data.get_item_opt("schedule", vm) {
+        wave.schedule.clear();
+        match value.try_into_value::<Vec<PyObjectRef>>(vm) {
+            Ok(entries) => {
+                for entry in entries {
+                    let pair = match entry.try_into_value::<Vec<PyObjectRef>>(vm) {
+                        Ok(pair) if (1..=2).contains(&pair.len()) => pair,
+                        _ => {
+                            report_wave_issue(issues, "danmaku schedule entry must be (time, spawnArgs)".to_string());
+                            continue;
+                        }
+                    };
+                    let time = match py_to_f32(vm, &pair[0]) {
+                        Some(time) => time,
+                        None => {
+                            report_wave_issue(issues, "danmaku schedule time must be number".to_string());
+                            continue;
+                        }
+                    };
+                    let args = match pair.get(1) {
+                        Some(args) if !vm.is_none(args) => {
+                            if args.clone().try_into_value::<PyDictRef>(vm).is_err() {
+                                report_wave_issue(issues, "danmaku schedule spawnArgs must be dict".to_string());
+                                continue;
+                            }
+                            Some(args.clone())
+                        }
+                        _ => None,
+                    };
+                    wave.schedule.push(WaveEntry { time, args });
+                }
+            }
+            Err(_) => report_wave_issue(issues, "danmaku schedule must be list".to_string()),
+        }
+        wave.schedule.sort_by(|a, b| a.time.total_cmp(&b.time));
+    }
+
+    wave.on_tick = module.get_attr("onTick", vm).ok();
+    if wave.on_tick.as_ref().is_some_and(|func| !func.is_callable()) {
+        report_wave_issue(issues, "danmaku onTick must be function".to_string());
+        wave.on_tick = None;
+    }
+}
+
+// spawn結果の展開
+pub fn spawn_results(vm: &VirtualMachine, result: PyObjectRef) -> Vec<PyObjectRef> {
+    if vm.is_none(&result) {
+        return Vec::new();
+    }
+    if let Ok(list) = result.clone().try_into_value::<PyListRef>(vm) {
+        return list.borrow_vec().to_vec();
+    }
+    vec![result]
+}
+
+// 既定の出現位置
+fn wave_origin(battle_box: &BattleBox) -> Vec2 {
+    Vec2::new(ORIGIN_X + battle_box.current.max.x - 40.0, ORIGIN_Y - battle_box.current.max.y + 40.0)
+}
+
+// 接触コールバック読込
+fn read_collide_callback(vm: &VirtualMachine, value: Option<PyObjectRef>) -> Option<PyObjectRef> {
+    let value = value.filter(|value| !vm.is_none(value))?;
+    if value.is_callable() {
+        Some(value)
+    } else {
+        println!("Warning: bullet onCollide must be callable");
+        None
+    }
+}
+
+// 弾生成
+fn spawn_bullet(
+    commands: &mut Commands,
+    project_assets: &ProjectAssets,
+    vm: &VirtualMachine,
+    wave: &DanmakuWave,
+    result: PyObjectRef,
+    origin: Vec2,
+    legacy: bool,
+) {
+    if let Some(spec) = &wave.rust_sim {
+        match result.clone().try_into_value::<PyDictRef>(vm) {
+            Ok(spawn_dict) => {
+                let mut context = build_expr_context();
+                let mut damage: Option<i32> = None;
+                let vars_dict = match spawn_dict.get_item_opt("vars", vm) {
+                    Ok(Some(value)) => match value.try_into_value::<PyDictRef>(vm) {
+                        Ok(dict) => Some(dict),
+                        Err(err) => {
+                            vm.print_exception(err.clone());
+                            println!("Warning: rustSim vars invalid");
+                            None
+                        }
+                    },
+                    _ => None,
+                };
+                let source_dict = vars_dict.as_ref().unwrap_or(&spawn_dict);
+                for (key, value) in source_dict {
+                    let key_name: String = match key.try_into_value(vm) {
+                        Ok(result) => result,
+                        Err(err) => {
+                            vm.print_exception(err.clone());
+                            continue;
+                        }
+                    };
+                    if let Some(value) = py_value_to_evalexpr(vm, &value) {
+                        let _ = context.set_value(key_name, value);
+                    }
+                }
+                if let Ok(Some(value)) = spawn_dict.get_item_opt("damage", vm) {
+                    if let Ok(result) = value.try_into_value::<i32>(vm) {
+                        damage = Some(result);
+                    }
+                }
+                if damage.is_none() {
+                    if let Some(vars_dict) = vars_dict.as_ref() {
+                        if let Ok(Some(value)) = vars_dict.get_item_opt("damage", vm) {
+                            if let Ok(result) = value.try_into_value::<i32>(vm) {
+                                damage = Some(result);
+                            }
+                        }
+                    }
+                }
+                if context.get_value("x").is_none() {
+                    let _ = context.set_value("x".to_string(), Value::Float(origin.x as f64));
+                }
+                if context.get_value("y").is_none() {
+                    let _ = context.set_value("y".to_string(), Value::Float(origin.y as f64));
+                }
+                let mut initial_texture = wave.texture_path.clone();
+                if let Some(Value::String(value)) = context.get_value("texture") {
+                    initial_texture = value.clone();
+                }
+                if let Ok(Some(value)) = spawn_dict.get_item_opt("texture", vm) {
+                    if let Ok(result) = value.try_into_value::<String>(vm) {
+                        initial_texture = result;
+                    }
+                }
+                if context.get_value("texture").is_none() {
+                    let _ = context.set_value("texture".to_string(), Value::String(initial_texture.clone()));
+                }
+                let _ = context.set_value("dt".to_string(), Value::Float(0.0));
+                let _ = context.set_value("t".to_string(), Value::Float(0.0));
+
+                let hitbox = match spawn_dict.get_item_opt("hitbox", vm) {
+                    Ok(Some(value)) => parse_hitbox(vm, &value, &mut Vec::new()).unwrap_or_default(),
+                    _ => Hitbox::default(),
+                };
+                // 色は辞書直下を優先して式変数にも反映
+                let color_value = match spawn_dict.get_item_opt("color", vm) {
+                    Ok(Some(value)) => {
+                        if let Some(expr_value) = py_value_to_evalexpr(vm, &value) {
+                            let _ = context.set_value("color".to_string(), expr_value);
+                        }
+                        Some(value)
+                    }
+                    _ => vars_dict.as_ref().and_then(|dict| dict.get_item_opt("color", vm).ok().flatten()),
+                };
+                let color = match &color_value {
+                    Some(value) => parse_bullet_color(vm, value, &mut Vec::new()),
+                    None => BulletColor::White,
+                };
+                let read_flag = |dict: &PyDictRef| dict.get_item_opt("destroyOnHit", vm).ok().flatten().and_then(|value| value.try_into_value::<bool>(vm).ok());
+                let destroy_on_hit = read_flag(&spawn_dict)
+                    .or_else(|| vars_dict.as_ref().and_then(read_flag))
+                    .unwrap_or(color == BulletColor::Green);
+                let on_collide = read_collide_callback(vm, spawn_dict.get_item_opt("onCollide", vm).ok().flatten());
+
+                let x = context.get_value("x").and_then(|value| value.as_number().ok()).unwrap_or(origin.x as f64) as f32;
+                let y = context.get_value("y").and_then(|value| value.as_number().ok()).unwrap_or(origin.y as f64) as f32;
+                commands.spawn((
+                    SpriteBundle {
+                        sprite: Sprite { image: project_assets.load(&initial_texture), color: color.tint(), ..default() },
+                        transform: Transform::from_xyz(x, y, 30.0).with_scale(Vec3::splat(1.0)),
+                        ..default()
+                    },
+                    ExpressionBullet {
+                        context,
+                        update_exprs: spec.update_exprs.clone(),
+                        delete_expr: spec.delete_expr.clone(),
+                        texture_expr: spec.texture_expr.clone(),
+                        last_texture: Some(initial_texture),
+                    },
+                    Bullet { damage: damage.unwrap_or(0), color, destroy_on_hit, on_collide, touching: false },
+                    hitbox,
+                    Cleanup,
+                ));
+                return;
+            }
+            Err(_) => println!("Warning: rustSim spawn must return dict"),
+        }
+    }
+
+    // 旧形式のspawn()は出現位置を設定
+    if legacy {
+        match result.get_attr("setPos", vm) {
+            Ok(set_pos) => {
+                if let Err(err) = set_pos.call((origin.x, origin.y), vm) {
+                    vm.print_exception(err.clone());
+                }
+            }
+            Err(err) => {
+                vm.print_exception(err.clone());
+            }
+        }
+    }
+
+    let damage = match result.get_attr("damage", vm) {
+        Ok(value) => match value.try_into_value::<i32>(vm) {
+            Ok(result) => result,
+            Err(err) => {
+                vm.print_exception(err.clone());
+                println!("Warning: bullet damage {:?}", err);
+                0
+            }
+        },
+        Err(err) => {
+            vm.print_exception(err.clone());
+            println!("Warning: bullet damage {:?}", err);
+            0
+        }
+    };
+    let hitbox = match result.get_attr("hitbox", vm) {
+        Ok(value) if !vm.is_none(&value) => parse_hitbox(vm, &value, &mut Vec::new()).unwrap_or_default(),
+        _ => Hitbox::default(),
+    };
+    let color = match result.get_attr("color", vm) {
+        Ok(value) if !vm.is_none(&value) => parse_bullet_color(vm, &value, &mut Vec::new()),
+        _ => BulletColor::White,
+    };
+    let destroy_on_hit = result
+        .get_attr("destroyOnHit", vm)
+        .ok()
+        .and_then(|value| value.try_into_value::<bool>(vm).ok())
+        .unwrap_or(color == BulletColor::Green);
+    let on_collide = read_collide_callback(vm, result.get_attr("onCollide", vm).ok());
+    let read_coord = |name: &'static str| result.get_attr(name, vm).ok().and_then(|value| py_to_f32(vm, &value));
+    let position = match (read_coord("x"), read_coord("y")) {
+        (Some(x), Some(y)) => Vec2::new(x, y),
+        _ => origin,
+    };
+
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite { image: project_assets.load(&wave.texture_path), color: color.tint(), ..default() },
+            transform: Transform::from_xyz(position.x, position.y, 30.0).with_scale(Vec3::splat(1.0)),
+            ..default()
+        },
+        PythonBullet { bullet_data: result.clone() },
+        Bullet { damage, color, destroy_on_hit, on_collide, touching: false },
+        hitbox,
+        Cleanup,
+    ));
+}
+
+// 一斉発射
+fn fire_burst(
+    commands: &mut Commands,
+    project_assets: &ProjectAssets,
+    vm: &VirtualMachine,
+    wave: &DanmakuWave,
+    args: Option<PyDictRef>,
+    battle_box: &BattleBox,
+) -> usize {
+    let Some(module) = &wave.module else { return 0 };
+    let spawn_func = match module.get_attr("spawn", vm) {
+        Ok(func) => func,
+        Err(err) => {
+            vm.print_exception(err.clone());
+            return 0;
+        }
+    };
+    let legacy = callable_arg_count(vm, &spawn_func) == Some(0);
+
+    let read_arg_i32 = |key: &str| args.as_ref().and_then(|args| read_option_i32(vm, args, key, "danmaku spawnArgs", false));
+    let read_arg_f32 = |key: &str| args.as_ref().and_then(|args| args.get_item_opt(key, vm).ok().flatten()).and_then(|value| py_to_f32(vm, &value));
+    let count = read_arg_i32("count").unwrap_or(wave.count).max(1);
+    let base_angle = read_arg_f32("angle").unwrap_or(wave.angle);
+    let spread = read_arg_f32("spread").unwrap_or(wave.spread);
+    let origin = wave_origin(battle_box);
+    let center = Vec2::new(ORIGIN_X + battle_box.current.center().x, ORIGIN_Y - battle_box.current.center().y);
+
+    let mut spawned = 0;
+    for index in 0..count {
+        let result = if legacy {
+            spawn_func.call((), vm)
+        } else {
+            // 引数を元にspawn情報を構築
+            let params = vm.ctx.new_dict();
+            if let Some(args) = &args {
+                for (key, value) in args {
+                    let _ = params.set_item(&*key, value, vm);
+                }
+            }
+            let angle = base_angle + spread * index as f32 / count as f32;
+            let _ = params.set_item("index", vm.new_pyobj(index), vm);
+            let _ = params.set_item("count", vm.new_pyobj(count), vm);
+            let _ = params.set_item("angle", vm.new_pyobj(angle), vm);
+            let _ = params.set_item("burst", vm.new_pyobj(wave.bursts), vm);
+            let _ = params.set_item("t", vm.new_pyobj(wave.elapsed), vm);
+            for (key, value) in [("x", origin.x), ("y", origin.y), ("centerX", center.x), ("centerY", center.y)] {
+                if !matches!(params.get_item_opt(key, vm), Ok(Some(_))) {
+                    let _ = params.set_item(key, vm.new_pyobj(value), vm);
+                }
+            }
+            spawn_func.call((params,), vm)
+        };
+        match result {
+            Ok(result) => {
+                for bullet in spawn_results(vm, result) {
+                    spawn_bullet(commands, project_assets, vm, wave, bullet, origin, legacy);
+                    spawned += 1;
+                }
+            }
+            Err(err) => {
+                vm.print_exception(err.clone());
+                println!("Warning: danmaku {} spawn {:?}", wave.script_name, err);
+                break;
+            }
+        }
+    }
+    spawned
+}
+
+// 弾幕ウェーブ進行
+pub fn danmaku_wave_update(
+    mut commands: Commands,
+    project_assets: ProjectAssets,
+    time: Res<Time>,
+    combat_state: Res<CombatState>,
+    battle_box: Res<BattleBox>,
+    python_runtime: NonSend<PythonRuntime>,
+    mut wave: ResMut<DanmakuWave>,
+) {
+    if combat_state.mn_fight != MainFightState::EnemyAttack || !wave.active {
+        return;
+    }
+    let wave = &mut *wave;
+
+    python_runtime.interpreter.enter(|vm| {
+        while let Some(entry) = wave.schedule.get(wave.next_entry).filter(|entry| entry.time <= wave.elapsed).cloned() {
+            wave.next_entry += 1;
+            let args = entry.args.and_then(|args| args.try_into_value::<PyDictRef>(vm).ok());
+            wave.spawned += fire_burst(&mut commands, &project_assets, vm, wave, args, &battle_box);
+            wave.bursts += 1;
+        }
+
+        let Some(on_tick) = wave.on_tick.clone() else { return };
+        match on_tick.call((wave.elapsed,), vm) {
+            Ok(result) => {
+                let origin = wave_origin(&battle_box);
+                for bullet in spawn_results(vm, result) {
+                    spawn_bullet(&mut commands, &project_assets, vm, wave, bullet, origin, false);
+                    wave.spawned += 1;
+                }
+            }
+            Err(err) => {
+                vm.print_exception(err.clone());
+                println!("Warning: danmaku {} onTick {:?}", wave.script_name, err);
+                wave.on_tick = None;
+            }
+        }
+    });
+
+    wave.elapsed += time.delta_secs();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::python_utils::eval_test_expr;
+
+    #[test]
+    fn wave_numbers_accept_ints() {
+        eval_test_expr("{'angle': 90, 'spread': 22.5}", |vm, value| {
+            let init_data: PyDictRef = value.try_into_value(vm).unwrap();
+            let mut issues = Vec::new();
+            assert_eq!(read_wave_number(vm, &init_data, "angle", &mut issues), Some(90.0));
+            assert_eq!(read_wave_number(vm, &init_data, "spread", &mut issues), Some(22.5));
+            assert!(issues.is_empty());
+        });
+    }
+
+    #[test]
+    fn wave_numbers_report_invalid_values() {
+        eval_test_expr("{'angle': 'up'}", |vm, value| {
+            let init_data: PyDictRef = value.try_into_value(vm).unwrap();
+            let mut issues = Vec::new();
+            assert_eq!(read_wave_number(vm, &init_data, "angle", &mut issues), None);
+            assert_eq!(read_wave_number(vm, &init_data, "spread", &mut issues), None);
+            assert_eq!(issues.len(), 1);
+        });
+    }
+
+    #[test]
+    fn wave_number_lists_accept_ints() {
+        eval_test_expr("{'box': [10, 20.5, 300, 400], 'soul': [1, 2, 3]}", |vm, value| {
+            let init_data: PyDictRef = value.try_into_value(vm).unwrap();
+            let mut issues = Vec::new();
+            assert_eq!(read_wave_numbers(vm, &init_data, "box", 4, &mut issues), Some(vec![10.0, 20.5, 300.0, 400.0]));
+            assert_eq!(read_wave_numbers(vm, &init_data, "soul", 2, &mut issues), None);
+            assert_eq!(issues.len(), 1);
+        });
+    }
+}
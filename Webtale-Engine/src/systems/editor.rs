@@ -1,7 +1,9 @@
 use bevy::prelude::*;
 use bevy_egui::{egui, EguiContexts};
 use crate::components::{EditorWindow, BattleScreenPreview};
-use crate::resources::{PlayerState, EditorState, EditorTab, EditorPreviewTexture, DanmakuPreviewTexture, BattleBox};
+use crate::project_archive;
+use crate::python_scripts;
+use crate::resources::{PlayerState, EditorState, EditorTab, EditorPreviewTexture, DanmakuPreviewTexture, BattleBox, CurrentProject, ProjectManifest, PythonRuntime, ScriptHotReload, CombatState, Encounter, LvTable};
 
 // エディタUI
 pub fn editor_ui_system(
@@ -13,6 +15,13 @@ pub fn editor_ui_system(
     _battle_box: ResMut<BattleBox>,
     mut bg_sprite_query: Query<&mut Visibility, With<BattleScreenPreview>>,
     danmaku_preview_texture: Res<DanmakuPreviewTexture>,
+    current_project: Res<CurrentProject>,
+    manifest: Option<Res<ProjectManifest>>,
+    python_runtime: NonSend<PythonRuntime>,
+    hot_reload: Res<ScriptHotReload>,
+    combat_state: Option<Res<CombatState>>,
+    encounter: Option<Res<Encounter>>,
+    lv_table: Option<Res<LvTable>>,
 ) {
     let Ok(editor_entity) = window_query.get_single() else { return };
 
@@ -41,6 +50,77 @@ pub fn editor_ui_system(
             ui.heading("Danmaku Settings");
             ui.separator();
 
+            egui::CollapsingHeader::new("Project")
+                .default_open(true)
+                .show(ui, |ui| {
+                    if editor_state.project_selection.is_empty() {
+                        editor_state.project_selection = current_project.name.clone();
+                    }
+
+                    ui.horizontal(|ui| {
+                        ui.label("Project:");
+                        egui::ComboBox::from_id_salt("project_selection")
+                            .selected_text(editor_state.project_selection.clone())
+                            .show_ui(ui, |ui| {
+                                for name in python_scripts::list_project_names() {
+                                    ui.selectable_value(&mut editor_state.project_selection, name.clone(), name);
+                                }
+                            });
+                    });
+
+                    ui.label(format!("Loaded: {}", current_project.name));
+                    if let Some(manifest) = manifest.as_ref() {
+                        ui.label(format!("Name: {}", manifest.name));
+                        if !manifest.author.is_empty() {
+                            ui.label(format!("Author: {}", manifest.author));
+                        }
+                        if !manifest.engine_version.is_empty() {
+                            ui.label(format!("Engine: {}+", manifest.engine_version));
+                        }
+                        ui.label(format!("Assets: {}", manifest.asset_roots.join(", ")));
+                    }
+                    if editor_state.project_selection != current_project.name {
+                        ui.label("Press Shift+R to switch");
+                    }
+                    if let Some(error) = hot_reload.last_error.as_ref() {
+                        ui.colored_label(egui::Color32::from_rgb(255, 96, 96), format!("Script error: {}", error));
+                    }
+
+                    // アーカイブ読み込み
+                    ui.horizontal(|ui| {
+                        ui.label("Archive:");
+                        ui.text_edit_singleline(&mut editor_state.archive_path);
+                        if ui.button("Import").clicked() {
+                            editor_state.archive_status = match project_archive::mount_archive_file(&editor_state.archive_path) {
+                                Ok(project_name) => {
+                                    editor_state.project_selection = project_name.clone();
+                                    format!("Mounted {}", project_name)
+                                }
+                                Err(message) => message,
+                            };
+                        }
+                    });
+
+                    // アーカイブ書き出し
+                    if ui.button("Export project").clicked() {
+                        let missing = project_archive::check_project_complete(&python_runtime, &current_project.name);
+                        editor_state.archive_status = if !missing.is_empty() {
+                            format!("Incomplete: {}", missing.join(", "))
+                        } else {
+                            let output_path = project_archive::default_export_path(&current_project.name);
+                            match project_archive::export_project(&current_project.name, &output_path) {
+                                Ok(count) => format!("Exported {} files to {}", count, output_path.display()),
+                                Err(message) => message,
+                            }
+                        };
+                    }
+                    if !editor_state.archive_status.is_empty() {
+                        ui.label(editor_state.archive_status.clone());
+                    }
+                });
+
+            ui.separator();
+
             egui::CollapsingHeader::new("Player Stats")
                 .default_open(true)
                 .show(ui, |ui| {
@@ -52,23 +132,16 @@ pub fn editor_ui_system(
 
                     ui.horizontal(|ui| {
                         ui.label("Level (LV):");
-                        let old_lv = player_state.lv;
-                        ui.add(egui::Slider::new(&mut player_state.lv, 1..=20));
-
-                        if old_lv != player_state.lv {
-                            let new_max_hp = if player_state.lv >= 20 {
-                                99.0
-                            } else {
-                                16.0 + (player_state.lv as f32 * 4.0)
-                            };
-                            
-                            let new_attack = 20.0 + ((player_state.lv - 1) as f32 * 2.0);
+                        let default_table = LvTable::default();
+                        let lv_table = lv_table.as_deref().unwrap_or(&default_table);
+                        let mut lv = player_state.lv;
+                        ui.add(egui::Slider::new(&mut lv, 1..=lv_table.max_lv()));
 
-                            player_state.max_hp = new_max_hp;
-                            player_state.hp = new_max_hp;
-                            player_state.attack = new_attack;
+                        if lv != player_state.lv {
+                            player_state.set_lv(lv, lv_table);
                         }
                     });
+                    ui.label(format!("EXP: {}", player_state.exp));
 
                     ui.horizontal(|ui| {
                         ui.label("Current HP:");
@@ -99,6 +172,36 @@ pub fn editor_ui_system(
 
             ui.separator();
 
+            egui::CollapsingHeader::new("Script Vars")
+                .default_open(false)
+                .show(ui, |ui| {
+                    let Some(combat_state) = combat_state.as_ref() else { return };
+
+                    ui.label("Battle");
+                    let mut battle_vars: Vec<_> = combat_state.battle_vars.iter().collect();
+                    battle_vars.sort_by(|a, b| a.0.cmp(b.0));
+                    for (key, value) in battle_vars {
+                        ui.label(format!("  {} = {}", key, value));
+                    }
+
+                    let Some(encounter) = encounter.as_ref() else { return };
+                    for (index, enemy_state) in encounter.enemies.iter().enumerate() {
+                        ui.label(format!("Enemy {} {} (Phase: {})", index, enemy_state.name, enemy_state.phase_name));
+                        let mut phase_names: Vec<_> = enemy_state.phase_vars.keys().collect();
+                        phase_names.sort();
+                        for phase_name in phase_names {
+                            ui.label(format!("Phase {}", phase_name));
+                            let mut vars: Vec<_> = enemy_state.phase_vars[phase_name].iter().collect();
+                            vars.sort_by(|a, b| a.0.cmp(b.0));
+                            for (key, value) in vars {
+                                ui.label(format!("  {} = {}", key, value));
+                            }
+                        }
+                    }
+                });
+
+            ui.separator();
+
             ui.heading("Bullet Pattern");
             if ui.button("Spawn Test Bullet").clicked() {
                 println!("Button Clicked!"); 
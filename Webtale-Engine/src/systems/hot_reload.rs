@@ -0,0 +1,164 @@
+use bevy::prelude::*;
+use rustpython_vm::compiler::Mode;
+use rustpython_vm::import::import_codeobj;
+use rustpython_vm::VirtualMachine;
+use std::collections::HashMap;
+use std::time::SystemTime;
+use crate::project_fs;
+use crate::resources::*;
+
+// 監視対象走査
+fn scan_scripts(project_name: &str) -> HashMap<String, SystemTime> {
+    project_fs::list_all_files(project_name)
+        .into_iter()
+        .filter(|path| path.ends_with(".py"))
+        .filter_map(|path| project_fs::modified_time(project_name, &path).map(|time| (path, time)))
+        .collect()
+}
+
+// sys.modules登録
+fn register_module(vm: &VirtualMachine, module_name: &str, module: &rustpython_vm::PyObjectRef) {
+    let result = vm
+        .import("sys", 0)
+        .and_then(|sys| sys.get_attr("modules", vm))
+        .and_then(|modules| modules.set_item(module_name, module.clone(), vm));
+    if let Err(err) = result {
+        vm.print_exception(err);
+    }
+}
+
+// 弾幕モジュール差し替え
+fn reload_danmaku_module(vm: &VirtualMachine, danmaku_scripts: &mut DanmakuScripts, relative_path: &str, module_name: &str, code: &str) -> Result<(), String> {
+    let code_obj = vm
+        .compile(code, Mode::Exec, relative_path.to_string())
+        .map_err(|err| format!("{} {}", relative_path, err))?;
+    if !danmaku_scripts.modules.contains_key(module_name) {
+        return Ok(());
+    }
+    if module_name != "api" {
+        if let Some(api_module) = danmaku_scripts.modules.get("api") {
+            register_module(vm, "api", api_module);
+        }
+    }
+    let module = import_codeobj(vm, module_name, code_obj, true).map_err(|err| {
+        vm.print_exception(err.clone());
+        format!("{} import failed", relative_path)
+    })?;
+
+    if module_name == "api" {
+        // API変更時は依存モジュール再読み込み
+        danmaku_scripts.modules.clear();
+        danmaku_scripts.rust_specs.clear();
+    } else {
+        danmaku_scripts.rust_specs.remove(module_name);
+    }
+    danmaku_scripts.modules.insert(module_name.to_string(), module);
+    Ok(())
+}
+
+// フェーズモジュール差し替え
+fn reload_phase_module(vm: &VirtualMachine, phase_scripts: &mut PhaseScripts, relative_path: &str, module_name: &str, code: &str) -> Result<(), String> {
+    let code_obj = vm
+        .compile(code, Mode::Exec, relative_path.to_string())
+        .map_err(|err| format!("{} {}", relative_path, err))?;
+    let is_api = module_name == "phase_api";
+    if is_api && phase_scripts.api.is_none() {
+        return Ok(());
+    }
+    if !is_api && !phase_scripts.modules.contains_key(module_name) {
+        return Ok(());
+    }
+    if !is_api {
+        if let Some(api_module) = phase_scripts.api.as_ref() {
+            register_module(vm, "phase_api", api_module);
+        }
+    }
+    let module = import_codeobj(vm, module_name, code_obj, true).map_err(|err| {
+        vm.print_exception(err.clone());
+        format!("{} import failed", relative_path)
+    })?;
+
+    if is_api {
+        // API変更時は依存モジュール再読み込み
+        phase_scripts.modules.clear();
+        phase_scripts.api = Some(module);
+    } else {
+        phase_scripts.modules.insert(module_name.to_string(), module);
+    }
+    Ok(())
+}
+
+// スクリプト変更反映
+fn reload_script(vm: &VirtualMachine, danmaku_scripts: &mut DanmakuScripts, phase_scripts: &mut PhaseScripts, project_name: &str, relative_path: &str) -> Result<(), String> {
+    let code = match project_fs::read_to_string(project_name, relative_path) {
+        Some(code) => code,
+        None => return Ok(()),
+    };
+    if let Some(file_name) = relative_path.strip_prefix("danmaku/") {
+        let module_name = file_name.trim_end_matches(".py");
+        return reload_danmaku_module(vm, danmaku_scripts, relative_path, module_name, &code);
+    }
+    if let Some(file_name) = relative_path.strip_prefix("phases/") {
+        let module_name = file_name.trim_end_matches(".py");
+        return reload_phase_module(vm, phase_scripts, relative_path, module_name, &code);
+    }
+
+    vm.compile(&code, Mode::Exec, relative_path.to_string())
+        .map_err(|err| format!("{} {}", relative_path, err))?;
+    println!("Warning: {} changed, press Shift+R to reload", relative_path);
+    Ok(())
+}
+
+// スクリプトホットリロード
+pub fn hot_reload_scripts(
+    time: Res<Time>,
+    mut hot_reload: ResMut<ScriptHotReload>,
+    current_project: Res<CurrentProject>,
+    mut danmaku_scripts: ResMut<DanmakuScripts>,
+    mut phase_scripts: ResMut<PhaseScripts>,
+    python_runtime: NonSend<PythonRuntime>,
+) {
+    if cfg!(target_arch = "wasm32") {
+        return;
+    }
+    if !hot_reload.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let project_name = current_project.name.clone();
+    let modified = scan_scripts(&project_name);
+    if hot_reload.project != project_name {
+        hot_reload.project = project_name;
+        hot_reload.modified = modified;
+        hot_reload.last_error = None;
+        return;
+    }
+
+    let mut changed: Vec<String> = modified
+        .iter()
+        .filter(|(path, time)| hot_reload.modified.get(*path) != Some(*time))
+        .map(|(path, _)| path.clone())
+        .collect();
+    hot_reload.modified = modified;
+    if changed.is_empty() {
+        return;
+    }
+    changed.sort();
+
+    python_runtime.interpreter.enter(|vm| {
+        for relative_path in &changed {
+            match reload_script(vm, &mut danmaku_scripts, &mut phase_scripts, &project_name, relative_path) {
+                Ok(()) => {
+                    println!("Reloaded {}", relative_path);
+                    if hot_reload.last_error.as_ref().is_some_and(|error| error.starts_with(relative_path.as_str())) {
+                        hot_reload.last_error = None;
+                    }
+                }
+                Err(message) => {
+                    println!("Warning: hot reload {}", message);
+                    hot_reload.last_error = Some(message);
+                }
+            }
+        }
+    });
+}
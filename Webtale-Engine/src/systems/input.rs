@@ -10,10 +10,13 @@ use bevy::window::WindowClosed;
 use bevy::window::WindowCloseRequested;
 use bevy_egui::EguiContexts;
 use bevy::sprite::Anchor;
+use rand::Rng;
 
 use crate::components::*;
 use crate::resources::*;
 use crate::constants::*;
+use crate::python_scripts;
+use crate::systems::combat::apply_item_heal;
 use crate::systems::setup::spawn_game_objects;
 
 #[derive(SystemParam)]
@@ -23,12 +26,26 @@ pub(crate) struct GlobalInputTextures<'w> {
     danmaku_preview_texture: ResMut<'w, DanmakuPreviewTexture>,
 }
 
+#[derive(SystemParam)]
+pub(crate) struct GlobalInputReload<'w> {
+    danmaku_scripts: ResMut<'w, DanmakuScripts>,
+    menu_render_cache: ResMut<'w, MenuRenderCache>,
+    current_project: ResMut<'w, CurrentProject>,
+    editor_state: ResMut<'w, EditorState>,
+}
+
 #[derive(SystemParam)]
 pub(crate) struct GlobalInputEvents<'w, 's> {
     window_closed_reader: EventReader<'w, 's, WindowClosed>,
     window_close_requested_reader: EventReader<'w, 's, WindowCloseRequested>,
 }
 
+#[derive(SystemParam)]
+pub(crate) struct MenuInputAssets<'w> {
+    asset_server: Res<'w, AssetServer>,
+    game_fonts: Res<'w, GameFonts>,
+}
+
 // エディタウィンドウ生成
 fn spawn_editor_window(
     commands: &mut Commands,
@@ -230,10 +247,8 @@ pub fn handle_global_input(
     mut window_query: Query<(Entity, &mut Window), With<bevy::window::PrimaryWindow>>,
     mut exit_writer: EventWriter<AppExit>,
     asset_server: Res<AssetServer>,
-    game_fonts: Res<GameFonts>,
     python_runtime: NonSend<PythonRuntime>,
-    mut danmaku_scripts: ResMut<DanmakuScripts>,
-    mut menu_render_cache: ResMut<MenuRenderCache>,
+    mut reload: GlobalInputReload,
     cleanup_query: Query<Entity, With<Cleanup>>,
     all_editor_entities: Query<Entity, With<EditorWindow>>, 
     open_editor_window_query: Query<Entity, (With<EditorWindow>, With<Window>)>, 
@@ -301,12 +316,24 @@ pub fn handle_global_input(
             commands.insert_resource(BattleBox {
                 current: Rect::new(32.0, 250.0, 602.0, 385.0),
                 target: Rect::new(32.0, 250.0, 602.0, 385.0),
+                ..default()
             });
 
-            danmaku_scripts.modules.clear();
-            danmaku_scripts.rust_specs.clear();
-            menu_render_cache.key = None;
-            spawn_game_objects(&mut commands, &asset_server, &game_fonts, &python_runtime);
+            // プロジェクト切替
+            let selection = reload.editor_state.project_selection.clone();
+            if !selection.is_empty() && selection != reload.current_project.name {
+                if python_scripts::project_exists(&selection) {
+                    reload.current_project.name = selection;
+                } else {
+                    println!("Warning: project missing projects/{}", selection);
+                }
+            }
+
+            reload.danmaku_scripts.modules.clear();
+            reload.danmaku_scripts.rust_specs.clear();
+            reload.menu_render_cache.key = None;
+            python_runtime.reset_script_modules();
+            spawn_game_objects(&mut commands, &asset_server, &python_runtime, &reload.current_project.name);
         }
     }
 
@@ -327,14 +354,16 @@ pub fn handle_global_input(
 // メニュー入力
 pub fn menu_input_system(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
+    menu_assets: MenuInputAssets,
     input: Res<ButtonInput<KeyCode>>,
     mut player_state: ResMut<PlayerState>,
-    enemy_state: Res<EnemyState>,
+    mut encounter: ResMut<Encounter>,
     mut menu_state: ResMut<MenuState>,
     mut combat_state: ResMut<CombatState>,
     mut typewriter_query: Query<(Entity, &mut Typewriter), With<MainDialogText>>,
-    act_commands_query: Query<&ActCommands, With<EnemyBody>>,
+    enemy_query: Query<(Entity, &EnemyBody), Without<SpareFade>>,
+    soul_query: Query<Entity, With<Soul>>,
+    manifest: Res<ProjectManifest>,
     menu_items_query: Query<Entity, With<MenuTextItem>>,
     mut egui_contexts: EguiContexts,
     editor_query: Query<Entity, (With<EditorWindow>, With<Window>)>,
@@ -356,6 +385,8 @@ pub fn menu_input_system(
     if combat_state.mn_fight != MainFightState::Menu || combat_state.my_fight != MessageFightState::None { return; }
     let layer = menu_state.menu_layer;
     let cursor_idx = menu_state.menu_coords[layer as usize] as usize;
+    let active_enemies = encounter.active_indices();
+    let act_count = encounter.target_enemy().map(|enemy| enemy.act_commands.len()).unwrap_or(0);
     
     if input.just_pressed(KeyCode::ArrowLeft) || input.just_pressed(KeyCode::KeyA) {
         if layer == MENU_LAYER_TOP {
@@ -375,11 +406,7 @@ pub fn menu_input_system(
         if layer == MENU_LAYER_TOP {
             menu_state.menu_coords[layer as usize] = (menu_state.menu_coords[layer as usize] + 1) % 4;
         } else if layer == MENU_LAYER_ACT_COMMAND {
-             if cursor_idx % 2 == 0 {
-                 if let Some(acts) = act_commands_query.iter().next() {
-                     if cursor_idx + 1 < acts.commands.len() { menu_state.menu_coords[layer as usize] += 1; }
-                 }
-             }
+             if cursor_idx % 2 == 0 && cursor_idx + 1 < act_count { menu_state.menu_coords[layer as usize] += 1; }
         } else if layer == MENU_LAYER_ITEM {
             let items_on_page = player_state.inventory.len().saturating_sub(menu_state.item_page * ITEMS_PER_PAGE).min(ITEMS_PER_PAGE);
             if cursor_idx % 2 == 0 && cursor_idx + 1 < items_on_page {
@@ -393,18 +420,18 @@ pub fn menu_input_system(
     if input.just_pressed(KeyCode::ArrowUp) || input.just_pressed(KeyCode::KeyW) {
          if layer == MENU_LAYER_ACT_COMMAND && cursor_idx >= 2 { menu_state.menu_coords[layer as usize] -= 2; }
          else if layer == MENU_LAYER_ITEM && cursor_idx >= 2 { menu_state.menu_coords[layer as usize] -= 2; }
-         else if layer == MENU_LAYER_MERCY && cursor_idx > 0 { menu_state.menu_coords[layer as usize] -= 1; }
+         else if (layer == MENU_LAYER_MERCY || layer == MENU_LAYER_FIGHT_TARGET || layer == MENU_LAYER_ACT_TARGET) && cursor_idx > 0 { menu_state.menu_coords[layer as usize] -= 1; }
     }
     if input.just_pressed(KeyCode::ArrowDown) || input.just_pressed(KeyCode::KeyS) {
          if layer == MENU_LAYER_ACT_COMMAND {
-            if let Some(acts) = act_commands_query.iter().next() {
-                 if cursor_idx + 2 < acts.commands.len() { menu_state.menu_coords[layer as usize] += 2; }
-            }
+            if cursor_idx + 2 < act_count { menu_state.menu_coords[layer as usize] += 2; }
+         } else if layer == MENU_LAYER_FIGHT_TARGET || layer == MENU_LAYER_ACT_TARGET {
+            if cursor_idx + 1 < active_enemies.len() { menu_state.menu_coords[layer as usize] += 1; }
          } else if layer == MENU_LAYER_ITEM {
             let items_on_page = player_state.inventory.len().saturating_sub(menu_state.item_page * ITEMS_PER_PAGE).min(ITEMS_PER_PAGE);
             if cursor_idx + 2 < items_on_page { menu_state.menu_coords[layer as usize] += 2; }
          } else if layer == MENU_LAYER_MERCY {
-             if cursor_idx < 1 { menu_state.menu_coords[layer as usize] += 1; }
+             if cursor_idx < 1 && encounter.can_flee() { menu_state.menu_coords[layer as usize] += 1; }
          }
     }
 
@@ -427,27 +454,32 @@ pub fn menu_input_system(
                 }
             },
             MENU_LAYER_FIGHT_TARGET => {
+                let Some(&target) = active_enemies.get(cursor_idx) else { return };
+                encounter.target = target;
                 combat_state.last_player_action = "attack".to_string();
                 combat_state.last_act_command = None;
                 combat_state.mn_fight = MainFightState::PlayerAttackBar; 
                 let box_center = gml_to_bevy(32.0 + (602.0-32.0)/2.0, 250.0 + (385.0-250.0)/2.0);
                 commands.spawn((
                     SpriteBundle {
-                        sprite: Sprite { image: asset_server.load("texture/attack/spr_target.png"), custom_size: Some(Vec2::new(566.0, 120.0)), ..default() },
+                        sprite: Sprite { image: menu_assets.asset_server.load("texture/attack/spr_target.png"), custom_size: Some(Vec2::new(566.0, 120.0)), ..default() },
                         transform: Transform::from_translation(box_center + Vec3::new(0.0, 0.0, Z_ATTACK_TARGET)),
                         ..default()
                     },
                     AttackTargetBox,
                     Cleanup,
                 ));
-                let bar_start_x = gml_to_bevy(32.0, 0.0).x;
+                // 武器ごとの攻撃バー
+                let bar_style = item_dict.equipped(&player_state, ItemKind::Weapon).map(|(_, info)| info.bar_style).unwrap_or(AttackBarStyle::Normal);
+                let bar_speed = bar_style.speed();
+                let bar_start_x = if bar_speed < 0.0 { gml_to_bevy(602.0, 0.0).x } else { gml_to_bevy(32.0, 0.0).x };
                 commands.spawn((
                     SpriteBundle {
-                        sprite: Sprite { image: asset_server.load("texture/attack/spr_targetchoice_1.png"), custom_size: Some(Vec2::new(14.0, 120.0)), ..default() },
+                        sprite: Sprite { image: menu_assets.asset_server.load("texture/attack/spr_targetchoice_1.png"), custom_size: Some(Vec2::new(14.0, 120.0)), ..default() },
                         transform: Transform::from_translation(Vec3::new(bar_start_x, box_center.y, Z_ATTACK_BAR)),
                         ..default()
                     },
-                    AttackBar { speed: 420.0, moving: true, flash_timer: Timer::from_seconds(0.08, TimerMode::Repeating), flash_state: true },
+                    AttackBar { speed: bar_speed, moving: true, flash_timer: Timer::from_seconds(0.08, TimerMode::Repeating), flash_state: true },
                     Cleanup,
                 ));
 
@@ -455,18 +487,21 @@ pub fn menu_input_system(
                 if let Ok((entity, _)) = typewriter_query.get_single_mut() { commands.entity(entity).despawn(); }
             },
             MENU_LAYER_ACT_TARGET => {
+                let Some(&target) = active_enemies.get(cursor_idx) else { return };
+                encounter.target = target;
                 menu_state.menu_layer = MENU_LAYER_ACT_COMMAND;
                 menu_state.menu_coords[MENU_LAYER_ACT_COMMAND as usize] = 0;
             },
             MENU_LAYER_ACT_COMMAND => {
                 let act_idx = menu_state.menu_coords[MENU_LAYER_ACT_COMMAND as usize] as usize;
                 let mut text_to_display = "* You did something.".to_string();
-                if let Some(acts) = act_commands_query.iter().next() {
-                    if act_idx < acts.commands.len() {
-                        let cmd_name = &acts.commands[act_idx];
+                if let Some(enemy_state) = encounter.target_enemy_mut() {
+                    if act_idx < enemy_state.act_commands.len() {
+                        let cmd_name = enemy_state.act_commands[act_idx].clone();
+                        enemy_state.record_act(&cmd_name);
                         combat_state.last_player_action = "act".to_string();
                         combat_state.last_act_command = Some(cmd_name.clone());
-                        if let Some(text) = enemy_state.act_texts.get(cmd_name) {
+                        if let Some(text) = enemy_state.act_texts.get(&cmd_name) {
                             text_to_display = text.clone();
                         } else if cmd_name == "Check" {
                             let enemy_name = if enemy_state.name.is_empty() { "ENEMY".to_string() } else { enemy_state.name.to_uppercase() };
@@ -488,7 +523,7 @@ pub fn menu_input_system(
                 
                 commands.spawn((
                     Text2d::new(""),
-                    TextFont { font: asset_server.load("font/8bitOperatorPlus-Bold.ttf"), font_size: 32.0 * TEXT_SCALE, ..default() },
+                    TextFont { font: menu_assets.game_fonts.dialog.clone(), font_size: 32.0 * TEXT_SCALE, ..default() },
                     TextColor(Color::WHITE),
                     Anchor::TopLeft,
                     Transform::from_translation(gml_to_bevy(52.0, 270.0) + Vec3::new(0.0, 0.0, Z_TEXT)),
@@ -503,30 +538,47 @@ pub fn menu_input_system(
                 if item_index < player_state.inventory.len() {
                     combat_state.last_player_action = "item".to_string();
                     combat_state.last_act_command = None;
-                    let item_name = player_state.inventory.remove(item_index);
-                    
-                    let (heal_amount, flavor_text) = if let Some(info) = item_dict.0.get(&item_name) {
-                        (info.heal_amount, info.text.clone())
-                    } else {
-                        (0, "...".to_string())
-                    };
-
-                    let old_hp = player_state.hp;
-                    player_state.hp = (player_state.hp + heal_amount as f32).min(player_state.max_hp);
-                    let recovered = (player_state.hp - old_hp) as i32;
-
-                    let mut text = format!("* You ate the {}.", item_name);
-
-                    if player_state.hp >= player_state.max_hp {
-                        text.push_str("\n* Your HP was maxed out!");
+                    let item_name = player_state.inventory[item_index].clone();
+                    let item_info = item_dict.0.get(&item_name).cloned();
+
+                    let text = if let Some(info) = item_info.as_ref().filter(|info| info.kind.is_equipment()) {
+                        // 装備入れ替え
+                        match item_dict.equipped(&player_state, info.kind) {
+                            Some((equipped_index, _)) => {
+                                let previous = std::mem::replace(&mut player_state.equipped_items[equipped_index], item_name.clone());
+                                player_state.inventory[item_index] = previous;
+                            }
+                            None => {
+                                player_state.inventory.remove(item_index);
+                                player_state.equipped_items.push(item_name.clone());
+                            }
+                        }
+                        let mut text = format!("* You equipped the {}.", item_name);
+                        if !info.text.is_empty() {
+                            text.push_str(&format!("\n* {}", info.text));
+                        }
+                        text
+                    } else if let Some(info) = item_info.as_ref().filter(|info| info.use_callback.is_some()) {
+                        // スクリプト効果はitem_use_updateで処理
+                        if info.consume {
+                            player_state.inventory.remove(item_index);
+                        }
+                        commands.spawn(PendingItemUse { item_name });
+                        combat_state.my_fight = MessageFightState::PlayerActionText;
+                        for entity in menu_items_query.iter() { commands.entity(entity).despawn(); }
+                        if let Ok((entity, _)) = typewriter_query.get_single_mut() { commands.entity(entity).despawn(); }
+                        return;
                     } else {
-                        if !flavor_text.is_empty() {
-                            text.push_str(&format!("\n* {}", flavor_text));
-                            text.push_str(&format!("\n* You recovered {} HP!", recovered));
-                        } else {
-                            text.push_str(&format!("\n* You recovered {} HP!", recovered));
+                        if item_info.as_ref().is_none_or(|info| info.consume) {
+                            player_state.inventory.remove(item_index);
                         }
-                    }
+                        let (heal_amount, flavor_text) = if let Some(info) = &item_info {
+                            (info.heal_amount, info.text.clone())
+                        } else {
+                            (0, "...".to_string())
+                        };
+                        apply_item_heal(&mut player_state, &item_name, heal_amount, &flavor_text)
+                    };
 
                     combat_state.my_fight = MessageFightState::PlayerActionText; 
                     
@@ -535,7 +587,7 @@ pub fn menu_input_system(
                     
                     commands.spawn((
                         Text2d::new(""),
-                        TextFont { font: asset_server.load("font/8bitOperatorPlus-Bold.ttf"), font_size: 32.0 * TEXT_SCALE, ..default() },
+                        TextFont { font: menu_assets.game_fonts.dialog.clone(), font_size: 32.0 * TEXT_SCALE, ..default() },
                         TextColor(Color::WHITE),
                         Anchor::TopLeft,
                         Transform::from_translation(gml_to_bevy(52.0, 270.0) + Vec3::new(0.0, 0.0, Z_TEXT)),
@@ -554,18 +606,54 @@ pub fn menu_input_system(
                     "flee".to_string()
                 };
                 let text = if mercy_idx == 0 {
-                    "* Spare... nothing happened.".to_string()
+                    // 見逃し可能な敵をすべて見逃す
+                    let mut spared_names = Vec::new();
+                    for index in encounter.active_indices() {
+                        let enemy_state = &mut encounter.enemies[index];
+                        if enemy_state.spareable {
+                            enemy_state.spared = true;
+                            spared_names.push(if enemy_state.name.is_empty() { "Enemy".to_string() } else { enemy_state.name.clone() });
+                        }
+                    }
+                    for (entity, body) in enemy_query.iter() {
+                        if encounter.enemies.get(body.index).is_some_and(|enemy| enemy.spared) {
+                            commands.entity(entity).insert(SpareFade { timer: Timer::from_seconds(1.0, TimerMode::Once) });
+                        }
+                    }
+                    if spared_names.is_empty() {
+                        "* Spare... nothing happened.".to_string()
+                    } else {
+                        format!("* You spared {}.", spared_names.join(" and "))
+                    }
                 } else {
-                    "* Escaped...".to_string()
+                    // 逃走判定
+                    let flee_chance = (manifest.flee_chance + manifest.flee_chance_per_turn * combat_state.turn_count as f32).clamp(0.0, 1.0);
+                    if rand::thread_rng().gen::<f32>() < flee_chance {
+                        combat_state.battle_result = Some(BattleResult { outcome: BattleOutcome::Fled, exp: 0, gold: 0, ..encounter.battle_result() });
+                        if let Ok(soul_entity) = soul_query.get_single() {
+                            commands.entity(soul_entity).insert(FleeingSoul { timer: Timer::from_seconds(1.0, TimerMode::Once) });
+                        }
+                        "* Escaped...".to_string()
+                    } else {
+                        "* You couldn't escape!".to_string()
+                    }
                 };
                 
-                combat_state.my_fight = MessageFightState::PlayerActionText;
                 for entity in menu_items_query.iter() { commands.entity(entity).despawn(); }
+                if combat_state.battle_result.is_some() {
+                    combat_state.mn_fight = MainFightState::Fled;
+                } else if encounter.active_indices().is_empty() {
+                    // 勝利文は勝利処理で表示
+                    combat_state.mn_fight = MainFightState::Victory;
+                    return;
+                } else {
+                    combat_state.my_fight = MessageFightState::PlayerActionText;
+                }
                 if let Ok((entity, _)) = typewriter_query.get_single_mut() { commands.entity(entity).despawn(); }
 
                 commands.spawn((
                     Text2d::new(""),
-                    TextFont { font: asset_server.load("font/8bitOperatorPlus-Bold.ttf"), font_size: 32.0 * TEXT_SCALE, ..default() },
+                    TextFont { font: menu_assets.game_fonts.dialog.clone(), font_size: 32.0 * TEXT_SCALE, ..default() },
                     TextColor(Color::WHITE),
                     Anchor::TopLeft,
                     Transform::from_translation(gml_to_bevy(52.0, 270.0) + Vec3::new(0.0, 0.0, Z_TEXT)),
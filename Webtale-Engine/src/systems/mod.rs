@@ -6,3 +6,4 @@ pub mod ui;
 pub mod combat;
 pub mod editor;
 pub mod phase;
+pub mod hot_reload;
@@ -3,11 +3,12 @@ use rustpython_vm::builtins::PyDictRef;
 use rustpython_vm::compiler::Mode;
 use rustpython_vm::convert::ToPyObject;
 use rustpython_vm::import::import_codeobj;
-use rustpython_vm::PyObjectRef;
+use rustpython_vm::{PyObjectRef, VirtualMachine};
+use crate::components::{GravityDirection, SoulMode};
 use crate::constants::*;
 use crate::python_scripts;
-use crate::python_utils::{read_option_string, read_option_vec_f32, read_option_vec_string};
-use crate::resources::{EnemyState, CombatState, MenuState, PythonRuntime, MainFightState, MessageFightState};
+use crate::python_utils::{py_dict_to_vars, read_option_bool, read_option_string, read_option_vec_f32, read_option_vec_string, vars_to_py_dict};
+use crate::resources::{EnemyState, CombatState, MenuState, PhaseScripts, PythonRuntime, MainFightState, MessageFightState};
 
 // 初期フェーズ取得
 fn resolve_initial_phase_from_api(project_name: &str, python_runtime: &PythonRuntime) -> Option<String> {
@@ -84,7 +85,7 @@ pub fn resolve_initial_phase(project_name: &str, requested: &str, python_runtime
 }
 
 // 吹き出しテクスチャ解決
-fn resolve_bubble_texture_name(name: &str) -> String {
+pub fn resolve_bubble_texture_name(name: &str) -> String {
     match name {
         "blconabove" => "texture/blcon/spr_blconabove.png",
         "blconbelow" => "texture/blcon/spr_blconbelow.png",
@@ -104,27 +105,156 @@ fn resolve_bubble_texture_name(name: &str) -> String {
     .to_string()
 }
 
+// フェーズコンテキスト生成
+pub fn build_phase_context(vm: &VirtualMachine, enemy_state: &EnemyState, combat_state: &CombatState, phase_name: &str, trigger: &str) -> PyDictRef {
+    let context = vm.ctx.new_dict();
+    let _ = context.set_item("turn", vm.new_pyobj(combat_state.turn_count), vm);
+    let _ = context.set_item("phaseTurn", vm.new_pyobj(enemy_state.phase_turn), vm);
+    let _ = context.set_item("enemyHp", vm.new_pyobj(enemy_state.hp), vm);
+    let _ = context.set_item("enemyMaxHp", vm.new_pyobj(enemy_state.max_hp), vm);
+    let _ = context.set_item("enemyName", vm.new_pyobj(enemy_state.name.clone()), vm);
+    let _ = context.set_item("spareable", vm.new_pyobj(enemy_state.spareable), vm);
+    let _ = context.set_item("fleeable", vm.new_pyobj(enemy_state.fleeable), vm);
+    let _ = context.set_item("phase", vm.new_pyobj(phase_name), vm);
+    let _ = context.set_item("trigger", vm.new_pyobj(trigger), vm);
+    let _ = context.set_item("isFirstTurn", vm.new_pyobj(combat_state.turn_count == 1), vm);
+    let _ = context.set_item("isPhaseStart", vm.new_pyobj(enemy_state.phase_turn == 1), vm);
+    let _ = context.set_item("isStart", vm.new_pyobj(trigger == "start"), vm);
+    let _ = context.set_item("isTurnStart", vm.new_pyobj(trigger == "turn"), vm);
+    let _ = context.set_item("isDamageApplied", vm.new_pyobj(trigger == "damage"), vm);
+    let last_action = if combat_state.last_player_action.is_empty() {
+        vm.ctx.none()
+    } else {
+        combat_state.last_player_action.clone().to_pyobject(vm)
+    };
+    let _ = context.set_item("lastPlayerAction", last_action, vm);
+    let last_act = match &combat_state.last_act_command {
+        Some(command) => command.clone().to_pyobject(vm),
+        None => vm.ctx.none(),
+    };
+    let _ = context.set_item("lastActCommand", last_act, vm);
+    let phase_vars = enemy_state.phase_vars.get(phase_name).cloned().unwrap_or_default();
+    let _ = context.set_item("vars", vars_to_py_dict(vm, &phase_vars).into(), vm);
+    let _ = context.set_item("battleVars", vars_to_py_dict(vm, &combat_state.battle_vars).into(), vm);
+    context
+}
+
+// スクリプト変数保存
+pub fn store_phase_vars(vm: &VirtualMachine, context: &PyDictRef, enemy_state: &mut EnemyState, combat_state: &mut CombatState, phase_name: &str) {
+    if let Ok(Some(value)) = context.get_item_opt("vars", vm) {
+        match value.try_into_value::<PyDictRef>(vm) {
+            Ok(dict) => {
+                let vars = py_dict_to_vars(vm, &dict, "phase vars");
+                enemy_state.phase_vars.insert(phase_name.to_string(), vars);
+            }
+            Err(err) => {
+                vm.print_exception(err.clone());
+                println!("Warning: phase vars must be dict");
+            }
+        }
+    }
+    if let Ok(Some(value)) = context.get_item_opt("battleVars", vm) {
+        match value.try_into_value::<PyDictRef>(vm) {
+            Ok(dict) => combat_state.battle_vars = py_dict_to_vars(vm, &dict, "battle vars"),
+            Err(err) => {
+                vm.print_exception(err.clone());
+                println!("Warning: battleVars must be dict");
+            }
+        }
+    }
+}
+
+// フェーズ状態適用
+pub fn apply_phase_state(vm: &VirtualMachine, state_dict: &PyDictRef, enemy_state: &mut EnemyState, combat_state: &CombatState, menu_state: &mut MenuState, label: &str) -> Option<String> {
+    if let Some(dialog_text) = read_option_string(vm, state_dict, "dialogText", label, false) {
+        enemy_state.dialog_text = dialog_text.clone();
+        if combat_state.mn_fight == MainFightState::Menu && combat_state.my_fight == MessageFightState::None && menu_state.menu_layer == MENU_LAYER_TOP {
+            menu_state.dialog_text = dialog_text;
+        }
+    }
+
+    if let Some(attacks) = read_option_vec_string(vm, state_dict, "attackPatterns", label, false) {
+        enemy_state.attacks = attacks;
+    }
+
+    if let Some(messages) = read_option_vec_string(vm, state_dict, "bubbleMessages", label, false) {
+        enemy_state.bubble_messages = messages;
+    }
+
+    if let Some(message) = read_option_string(vm, state_dict, "bubbleMessage", label, false) {
+        if !message.is_empty() {
+            enemy_state.bubble_message_override = Some(message);
+        }
+    }
+
+    if let Some(texture) = read_option_string(vm, state_dict, "bubbleTexture", label, false) {
+        enemy_state.bubble_texture = resolve_bubble_texture_name(&texture);
+    }
+
+    if let Some(pos) = read_option_vec_f32(vm, state_dict, "bubblePosition", label, false) {
+        if pos.len() == 2 {
+            enemy_state.bubble_pos_override = Some(Vec2::new(pos[0], pos[1]));
+        } else {
+            println!("Warning: {} bubblePosition invalid", label);
+        }
+    }
+
+    if let Some(spareable) = read_option_bool(vm, state_dict, "spareable", label, false) {
+        enemy_state.spareable = spareable;
+    }
+
+    if let Some(fleeable) = read_option_bool(vm, state_dict, "fleeable", label, false) {
+        enemy_state.fleeable = fleeable;
+    }
+
+    if let Some(name) = read_option_string(vm, state_dict, "soulMode", label, false) {
+        match SoulMode::from_name(&name) {
+            Some(mode) => enemy_state.soul_mode = mode,
+            None => println!("Warning: {} soulMode unknown {}", label, name),
+        }
+    }
+
+    if let Some(name) = read_option_string(vm, state_dict, "soulGravity", label, false) {
+        match GravityDirection::from_name(&name) {
+            Some(gravity) => enemy_state.soul_gravity = gravity,
+            None => println!("Warning: {} soulGravity unknown {}", label, name),
+        }
+    }
+
+    read_option_string(vm, state_dict, "nextPhase", label, false)
+}
+
 // フェーズ更新
-pub fn apply_phase_update(enemy_state: &mut EnemyState, combat_state: &mut CombatState, menu_state: &mut MenuState, project_name: &str, trigger: &str, python_runtime: &PythonRuntime) -> Option<String> {
-    if combat_state.phase_name.is_empty() {
+pub fn apply_phase_update(enemy_state: &mut EnemyState, combat_state: &mut CombatState, menu_state: &mut MenuState, phase_scripts: &mut PhaseScripts, project_name: &str, trigger: &str, python_runtime: &PythonRuntime) -> Option<String> {
+    if enemy_state.phase_name.is_empty() {
         return None;
     }
 
-    let phase_name = combat_state.phase_name.clone();
-    let script_content = match python_scripts::get_phase_script(project_name, &phase_name) {
-        Some(content) => content,
-        None => {
-            println!("Warning: phase script missing projects/{}/phases/{}.py", project_name, phase_name);
-            return None;
+    let phase_name = enemy_state.phase_name.clone();
+    let cached_module = phase_scripts.modules.get(&phase_name).cloned();
+    let cached_api = phase_scripts.api.clone();
+    let script_content = if cached_module.is_none() {
+        match python_scripts::get_phase_script(project_name, &phase_name) {
+            Some(content) => Some(content),
+            None => {
+                println!("Warning: phase script missing projects/{}/phases/{}.py", project_name, phase_name);
+                return None;
+            }
         }
+    } else {
+        None
     };
 
-    let api_content = match python_scripts::get_phase_api_script(project_name) {
-        Some(content) => content,
-        None => {
-            println!("Warning: phase api missing projects/{}/phases/phase_api.py", project_name);
-            return None;
+    let api_content = if cached_api.is_none() {
+        match python_scripts::get_phase_api_script(project_name) {
+            Some(content) => Some(content),
+            None => {
+                println!("Warning: phase api missing projects/{}/phases/phase_api.py", project_name);
+                return None;
+            }
         }
+    } else {
+        None
     };
 
     let mut next_phase: Option<String> = None;
@@ -147,9 +277,20 @@ pub fn apply_phase_update(enemy_state: &mut EnemyState, combat_state: &mut Comba
             }
         };
 
-        let api_module = match run_module(&api_content, "phase_api.py", "phase_api") {
+        let api_module = match cached_api {
             Some(module) => module,
-            None => return,
+            None => {
+                let api_content = match api_content.as_deref() {
+                    Some(content) => content,
+                    None => return,
+                };
+                let module = match run_module(api_content, "phase_api.py", "phase_api") {
+                    Some(module) => module,
+                    None => return,
+                };
+                phase_scripts.api = Some(module.clone());
+                module
+            }
         };
 
         let sys = match vm.import("sys", 0) {
@@ -171,30 +312,7 @@ pub fn apply_phase_update(enemy_state: &mut EnemyState, combat_state: &mut Comba
             return;
         }
 
-        let context = vm.ctx.new_dict();
-        let _ = context.set_item("turn", vm.new_pyobj(combat_state.turn_count), vm);
-        let _ = context.set_item("phaseTurn", vm.new_pyobj(combat_state.phase_turn), vm);
-        let _ = context.set_item("enemyHp", vm.new_pyobj(enemy_state.hp), vm);
-        let _ = context.set_item("enemyMaxHp", vm.new_pyobj(enemy_state.max_hp), vm);
-        let _ = context.set_item("enemyName", vm.new_pyobj(enemy_state.name.clone()), vm);
-        let _ = context.set_item("phase", vm.new_pyobj(phase_name.clone()), vm);
-        let _ = context.set_item("trigger", vm.new_pyobj(trigger), vm);
-        let _ = context.set_item("isFirstTurn", vm.new_pyobj(combat_state.turn_count == 1), vm);
-        let _ = context.set_item("isPhaseStart", vm.new_pyobj(combat_state.phase_turn == 1), vm);
-        let _ = context.set_item("isStart", vm.new_pyobj(trigger == "start"), vm);
-        let _ = context.set_item("isTurnStart", vm.new_pyobj(trigger == "turn"), vm);
-        let _ = context.set_item("isDamageApplied", vm.new_pyobj(trigger == "damage"), vm);
-        let last_action = if combat_state.last_player_action.is_empty() {
-            vm.ctx.none()
-        } else {
-            combat_state.last_player_action.clone().to_pyobject(vm)
-        };
-        let _ = context.set_item("lastPlayerAction", last_action, vm);
-        let last_act = match &combat_state.last_act_command {
-            Some(command) => command.clone().to_pyobject(vm),
-            None => vm.ctx.none(),
-        };
-        let _ = context.set_item("lastActCommand", last_act, vm);
+        let context = build_phase_context(vm, enemy_state, combat_state, &phase_name, trigger);
 
         match api_module.get_attr("reset", vm) {
             Ok(reset_func) => {
@@ -207,9 +325,20 @@ pub fn apply_phase_update(enemy_state: &mut EnemyState, combat_state: &mut Comba
             }
         }
 
-        let phase_module = match run_module(&script_content, &format!("{}.py", phase_name), &phase_name) {
+        let phase_module = match cached_module {
             Some(module) => module,
-            None => return,
+            None => {
+                let script_content = match script_content.as_deref() {
+                    Some(content) => content,
+                    None => return,
+                };
+                let module = match run_module(script_content, &format!("{}.py", phase_name), &phase_name) {
+                    Some(module) => module,
+                    None => return,
+                };
+                phase_scripts.modules.insert(phase_name.clone(), module.clone());
+                module
+            }
         };
 
         let update_func = match phase_module.get_attr("update", vm) {
@@ -221,7 +350,9 @@ pub fn apply_phase_update(enemy_state: &mut EnemyState, combat_state: &mut Comba
             }
         };
 
-        let update_result = match vm.invoke(&update_func, (context.clone(),)) {
+        let update_result = vm.invoke(&update_func, (context.clone(),));
+        store_phase_vars(vm, &context, enemy_state, combat_state, &phase_name);
+        let update_result = match update_result {
             Ok(result) => result,
             Err(err) => {
                 vm.print_exception(err.clone());
@@ -229,52 +360,15 @@ pub fn apply_phase_update(enemy_state: &mut EnemyState, combat_state: &mut Comba
             }
         };
 
-        let mut apply_state = |state_dict: &PyDictRef| {
-            if let Some(dialog_text) = read_option_string(vm, state_dict, "dialogText", "phase", false) {
-                enemy_state.dialog_text = dialog_text.clone();
-                if combat_state.mn_fight == MainFightState::Menu && combat_state.my_fight == MessageFightState::None && menu_state.menu_layer == MENU_LAYER_TOP {
-                    menu_state.dialog_text = dialog_text;
-                }
-            }
-
-            if let Some(attacks) = read_option_vec_string(vm, state_dict, "attackPatterns", "phase", false) {
-                enemy_state.attacks = attacks;
-            }
-
-            if let Some(messages) = read_option_vec_string(vm, state_dict, "bubbleMessages", "phase", false) {
-                enemy_state.bubble_messages = messages;
-            }
-
-            if let Some(message) = read_option_string(vm, state_dict, "bubbleMessage", "phase", false) {
-                if !message.is_empty() {
-                    enemy_state.bubble_message_override = Some(message);
-                }
-            }
-
-            if let Some(texture) = read_option_string(vm, state_dict, "bubbleTexture", "phase", false) {
-                enemy_state.bubble_texture = resolve_bubble_texture_name(&texture);
-            }
-
-            if let Some(pos) = read_option_vec_f32(vm, state_dict, "bubblePosition", "phase", false) {
-                if pos.len() == 2 {
-                    enemy_state.bubble_pos_override = Some(Vec2::new(pos[0], pos[1]));
-                } else {
-                    println!("Warning: phase bubblePosition invalid");
-                }
-            }
-
-            next_phase = read_option_string(vm, state_dict, "nextPhase", "phase", false);
-        };
-
         if let Ok(dict) = update_result.try_into_value::<PyDictRef>(vm) {
-            apply_state(&dict);
+            next_phase = apply_phase_state(vm, &dict, enemy_state, combat_state, menu_state, "phase");
             return;
         }
 
         match api_module.get_attr("getState", vm) {
             Ok(get_state) => match vm.invoke(&get_state, ()) {
                 Ok(state_result) => match state_result.try_into_value::<PyDictRef>(vm) {
-                    Ok(dict) => apply_state(&dict),
+                    Ok(dict) => next_phase = apply_phase_state(vm, &dict, enemy_state, combat_state, menu_state, "phase"),
                     Err(err) => vm.print_exception(err),
                 },
                 Err(err) => vm.print_exception(err),
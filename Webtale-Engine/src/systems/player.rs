@@ -10,6 +10,10 @@ pub fn soul_position_sync(
     menu_state: Res<MenuState>,
     mut soul_query: Query<&mut Transform, With<Soul>>,
 ) {
+    if combat_state.mn_fight == MainFightState::Fled {
+        return;
+    }
+
     if (combat_state.mn_fight != MainFightState::Menu && combat_state.mn_fight != MainFightState::EnemyAttack) || combat_state.my_fight != MessageFightState::None { 
         if let Ok(mut t) = soul_query.get_single_mut() {
             t.translation = gml_to_bevy(-200.0, 0.0); 
@@ -39,7 +43,8 @@ pub fn soul_position_sync(
         transform.translation = pos + Vec3::new(0.0, 0.0, Z_SOUL);
 
     } else if layer == MENU_LAYER_FIGHT_TARGET || layer == MENU_LAYER_ACT_TARGET {
-        let pos = gml_to_bevy(text_start_x, text_start_y);
+        let idx = menu_state.menu_coords[layer as usize] as usize;
+        let pos = gml_to_bevy(text_start_x, text_start_y + (idx as f32) * 32.0);
         transform.translation = pos + Vec3::new(0.0, 0.0, Z_SOUL);
 
     } else if layer == MENU_LAYER_ACT_COMMAND || layer == MENU_LAYER_ITEM {
@@ -60,6 +65,88 @@ pub fn soul_position_sync(
     }
 }
 
+// 足場の着地面(落下方向の深さ)
+fn platform_surface(rect: Rect, down: Vec2) -> f32 {
+    rect.min.dot(down).min(rect.max.dot(down))
+}
+
+// 青ソウル(重力)移動
+fn blue_soul_step(motion: &mut SoulMotion, battle_box: &BattleBox, mut local: Vec2, input_vec: Vec2, delta: f32, dt: f32, soul_radius: f32) -> (Vec2, bool) {
+    let box_half = battle_box.current.half_size();
+    let half_size = (box_half - Vec2::splat(soul_radius)).max(Vec2::ZERO);
+    let down = motion.gravity.vector();
+    let side = Vec2::new(down.y.abs(), down.x.abs());
+    let jump_held = input_vec.dot(-down) > 0.0;
+
+    // 乗っている足場の移動に追従し、面の上へ合わせ直す
+    let riding = motion
+        .platform
+        .filter(|_| motion.platform_revision == battle_box.platform_revision)
+        .and_then(|index| battle_box.platforms.get(index));
+    if let Some(platform) = riding {
+        let velocity = Vec2::new(platform.velocity.x, -platform.velocity.y);
+        local += side * velocity.dot(side) * dt;
+        local += down * (platform_surface(platform.local_rect(box_half), down) - soul_radius - local.dot(down));
+    }
+
+    // 重力と垂直な方向だけ移動
+    local += side * input_vec.dot(side) * delta;
+
+    if motion.grounded && jump_held && !motion.slamming {
+        motion.fall_speed = -SOUL_JUMP_SPEED;
+    }
+    // 離すと上昇を打ち切る
+    if !jump_held && motion.fall_speed < -SOUL_JUMP_CUT_SPEED {
+        motion.fall_speed = -SOUL_JUMP_CUT_SPEED;
+    }
+    if !motion.slamming {
+        motion.fall_speed = (motion.fall_speed + SOUL_GRAVITY * dt).min(SOUL_MAX_FALL_SPEED);
+    }
+    let feet_before = local.dot(down) + soul_radius;
+    local += down * motion.fall_speed * dt;
+    motion.grounded = false;
+    motion.platform = None;
+    let mut landed = false;
+
+    // 足場は重力と直交する向きのものに落下中のみ着地
+    if motion.fall_speed >= 0.0 {
+        let feet_after = local.dot(down) + soul_radius;
+        let along = local.dot(side);
+        for (index, platform) in battle_box.platforms.iter().enumerate() {
+            if platform.vertical != (down.y == 0.0) {
+                continue;
+            }
+            let rect = platform.local_rect(box_half);
+            let depth = platform_surface(rect, down);
+            let on_span = along >= rect.min.dot(side) - soul_radius / 2.0 && along <= rect.max.dot(side) + soul_radius / 2.0;
+            if on_span && feet_before <= depth + 0.5 && feet_after >= depth {
+                local += down * (depth - soul_radius - local.dot(down));
+                motion.fall_speed = 0.0;
+                motion.grounded = true;
+                motion.platform = Some(index);
+                motion.platform_revision = battle_box.platform_revision;
+                landed = motion.slamming;
+                motion.slamming = false;
+                break;
+            }
+        }
+    }
+
+    // ボックスの辺に着地
+    let limit = half_size.dot(down.abs());
+    let depth = local.dot(down);
+    if depth >= limit {
+        motion.fall_speed = 0.0;
+        motion.grounded = true;
+        motion.platform = None;
+        landed |= motion.slamming;
+        motion.slamming = false;
+    } else if depth <= -limit && motion.fall_speed < 0.0 {
+        motion.fall_speed = 0.0;
+    }
+    (local.clamp(-half_size, half_size), landed)
+}
+
 // ソウル移動
 pub fn soul_combat_movement(
     time: Res<Time>,
@@ -67,7 +154,8 @@ pub fn soul_combat_movement(
     combat_state: Res<CombatState>,
     player_state: Res<PlayerState>,
     battle_box: Res<BattleBox>,
-    mut query: Query<&mut Transform, With<Soul>>,
+    mut screen_shake: ResMut<ScreenShake>,
+    mut query: Query<(&mut Transform, &mut SoulMotion), With<Soul>>,
     mut egui_contexts: EguiContexts,
     editor_query: Query<Entity, (With<EditorWindow>, With<Window>)>,
     editor_state: Option<Res<EditorState>>,
@@ -86,7 +174,7 @@ pub fn soul_combat_movement(
 
     if combat_state.mn_fight != MainFightState::EnemyAttack { return; }
 
-    let mut transform = query.single_mut();
+    let (mut transform, mut motion) = query.single_mut();
     
     let speed = player_state.speed;
     
@@ -98,17 +186,141 @@ pub fn soul_combat_movement(
     if input.pressed(KeyCode::ArrowLeft)  || input.pressed(KeyCode::KeyA) { move_vec.x -= 1.0; }
     if input.pressed(KeyCode::ArrowRight) || input.pressed(KeyCode::KeyD) { move_vec.x += 1.0; }
 
+    // 青弾・橙弾の判定用
+    motion.moving = move_vec != Vec3::ZERO;
+
+    let soul_radius = 8.0;
+    let box_center = Vec2::new(ORIGIN_X + battle_box.current.center().x, ORIGIN_Y - battle_box.current.center().y);
+    let box_half = battle_box.current.half_size();
+    let half_size = (box_half - Vec2::splat(soul_radius)).max(Vec2::ZERO);
+    let rotation = Rot2::degrees(battle_box.rotation);
+    let top_left = Vec2::new(-box_half.x, box_half.y);
+    let box_origin = box_center + rotation * top_left;
+
+    if motion.mode == SoulMode::Blue {
+        // 足場に乗っていれば前フレームのボックス左上基準で追従
+        let local = if motion.platform.is_some() {
+            rotation.inverse() * (transform.translation.truncate() - motion.box_origin) + top_left
+        } else {
+            rotation.inverse() * (transform.translation.truncate() - box_center)
+        };
+        let (local, landed) = blue_soul_step(&mut motion, &battle_box, local, move_vec.truncate(), delta, time.delta_secs(), soul_radius);
+        if landed {
+            screen_shake.timer = 0.2;
+            screen_shake.intensity = 3.0;
+        }
+        let position = box_center + rotation * local;
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+        motion.box_origin = box_origin;
+        return;
+    }
+    motion.box_origin = box_origin;
+
     if move_vec != Vec3::ZERO {
         move_vec = move_vec.normalize() * delta;
         transform.translation += move_vec;
     }
 
-    let soul_radius = 8.0;
-    let box_left = ORIGIN_X + battle_box.current.min.x + soul_radius;
-    let box_right = ORIGIN_X + battle_box.current.max.x - soul_radius;
-    let box_top = ORIGIN_Y - battle_box.current.min.y - soul_radius;
-    let box_bottom = ORIGIN_Y - battle_box.current.max.y + soul_radius;
+    // 回転したボックスの内側に制限
+    let local = rotation.inverse() * (transform.translation.truncate() - box_center);
+    let clamped = box_center + rotation * local.clamp(-half_size, half_size);
+
+    transform.translation.x = clamped.x;
+    transform.translation.y = clamped.y;
+}
+
+// ソウルモード表示
+pub fn soul_mode_sprite_update(
+    asset_server: Res<AssetServer>,
+    mut query: Query<(&mut SoulMotion, &mut Sprite)>,
+) {
+    for (mut motion, mut sprite) in query.iter_mut() {
+        // 表示中のモードから変わった時だけ差し替え
+        if motion.sprite_mode == Some(motion.mode) {
+            continue;
+        }
+        let path = match motion.mode {
+            SoulMode::Red => "texture/heart/spr_heart_0.png",
+            SoulMode::Blue => "texture/heart/spr_heart_blue_0.png",
+        };
+        sprite.image = asset_server.load(path);
+        motion.sprite_mode = Some(motion.mode);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT: f32 = 0.05;
+    const RADIUS: f32 = 8.0;
 
-    transform.translation.x = transform.translation.x.clamp(box_left, box_right);
-    transform.translation.y = transform.translation.y.clamp(box_bottom, box_top);
+    // 200x100のボックス
+    fn test_box(platforms: Vec<BoxPlatform>) -> BattleBox {
+        let rect = Rect::new(0.0, 0.0, 200.0, 100.0);
+        let mut battle_box = BattleBox { current: rect, target: rect, ..Default::default() };
+        battle_box.set_platforms(platforms);
+        battle_box
+    }
+
+    fn platform(x: f32, y: f32, width: f32, vx: f32, vertical: bool) -> BoxPlatform {
+        BoxPlatform { position: Vec2::new(x, y), width, velocity: Vec2::new(vx, 0.0), vertical }
+    }
+
+    fn falling(gravity: GravityDirection) -> SoulMotion {
+        SoulMotion { mode: SoulMode::Blue, gravity, fall_speed: 100.0, ..Default::default() }
+    }
+
+    #[test]
+    fn local_rect_uses_box_top_left() {
+        let half = Vec2::new(100.0, 50.0);
+        assert_eq!(platform(50.0, 60.0, 40.0, 0.0, false).local_rect(half), Rect::new(-50.0, -10.0 - PLATFORM_THICKNESS, -10.0, -10.0));
+        assert_eq!(platform(150.0, 20.0, 60.0, 0.0, true).local_rect(half), Rect::new(50.0, -30.0, 50.0 + PLATFORM_THICKNESS, 30.0));
+    }
+
+    #[test]
+    fn blue_soul_lands_on_box_floor() {
+        let battle_box = test_box(vec![]);
+        let mut motion = falling(GravityDirection::Down);
+        let (local, _) = blue_soul_step(&mut motion, &battle_box, Vec2::new(0.0, -40.0), Vec2::ZERO, 0.0, DT, RADIUS);
+        assert_eq!(local, Vec2::new(0.0, -42.0));
+        assert!(motion.grounded);
+        assert_eq!(motion.platform, None);
+    }
+
+    #[test]
+    fn blue_soul_lands_on_platform_and_rides_it() {
+        let battle_box = test_box(vec![platform(50.0, 60.0, 40.0, 20.0, false)]);
+        let mut motion = falling(GravityDirection::Down);
+        let (local, _) = blue_soul_step(&mut motion, &battle_box, Vec2::new(-30.0, -1.0), Vec2::ZERO, 0.0, DT, RADIUS);
+        assert_eq!(local, Vec2::new(-30.0, -2.0));
+        assert_eq!((motion.platform, motion.platform_revision), (Some(0), battle_box.platform_revision));
+
+        // 足場の横移動に追従
+        let (local, _) = blue_soul_step(&mut motion, &battle_box, local, Vec2::ZERO, 0.0, DT, RADIUS);
+        assert_eq!(local, Vec2::new(-29.0, -2.0));
+        assert!(motion.grounded);
+    }
+
+    #[test]
+    fn replaced_platforms_are_not_followed() {
+        let mut battle_box = test_box(vec![platform(50.0, 60.0, 40.0, 20.0, false)]);
+        let mut motion = falling(GravityDirection::Down);
+        let (local, _) = blue_soul_step(&mut motion, &battle_box, Vec2::new(-30.0, -1.0), Vec2::ZERO, 0.0, DT, RADIUS);
+        // 同じ番号に別の足場が入っても古い番号では追従しない
+        battle_box.set_platforms(vec![platform(50.0, 60.0, 40.0, 80.0, false)]);
+        let (local, _) = blue_soul_step(&mut motion, &battle_box, local, Vec2::ZERO, 0.0, DT, RADIUS);
+        assert_eq!(local.x, -30.0);
+        assert_eq!(motion.platform_revision, battle_box.platform_revision);
+    }
+
+    #[test]
+    fn sideways_gravity_uses_vertical_platforms() {
+        let battle_box = test_box(vec![platform(150.0, 20.0, 60.0, 0.0, false), platform(150.0, 20.0, 60.0, 0.0, true)]);
+        let mut motion = falling(GravityDirection::Right);
+        let (local, _) = blue_soul_step(&mut motion, &battle_box, Vec2::new(40.0, 0.0), Vec2::ZERO, 0.0, DT, RADIUS);
+        assert_eq!(local, Vec2::new(42.0, 0.0));
+        assert_eq!(motion.platform, Some(1));
+    }
 }
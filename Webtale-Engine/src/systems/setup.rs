@@ -3,12 +3,17 @@ use bevy::sprite::Anchor;
 use rustpython_vm::builtins::PyDictRef;
 use rustpython_vm::compiler::Mode;
 use rustpython_vm::scope::Scope;
+use rustpython_vm::{PyObjectRef, VirtualMachine};
 use std::collections::HashMap;
 use crate::components::*;
 use crate::constants::*;
+use crate::project;
+use crate::project_archive;
+use crate::project_fs;
 use crate::python_scripts;
-use crate::python_utils::{read_option_f32, read_option_i32, read_option_string, read_option_vec_string};
+use crate::python_utils::{py_to_f32, read_option_bool, read_option_f32, read_option_i32, read_option_string, read_option_vec_string};
 use crate::resources::*;
+use crate::systems::combat;
 use crate::systems::phase;
 
 // 初期セットアップ
@@ -16,6 +21,7 @@ pub fn setup(
     mut commands: Commands, 
     asset_server: Res<AssetServer>,
     python_runtime: NonSend<PythonRuntime>,
+    current_project: Res<CurrentProject>,
     mut window_query: Query<&mut Window, With<bevy::window::PrimaryWindow>>,
 ) {
     if let Ok(mut window) = window_query.get_single_mut() {
@@ -27,69 +33,107 @@ pub fn setup(
         MainCamera,
     ));
 
-    let font_main = asset_server.load("font/Mars_Needs_Cunnilingus.ttf");
-    let font_dialog = asset_server.load("font/8bitOperatorPlus-Bold.ttf");
-    let font_hp_label = asset_server.load("font/8-BIT_WO.ttf");
-    let font_damage = asset_server.load("font/hachicro.TTF");
-
-    let game_fonts = GameFonts {
-        main: font_main.clone(),
-        dialog: font_dialog.clone(),
-        hp_label: font_hp_label.clone(),
-        damage: font_damage.clone(), 
-    };
-
-    spawn_game_objects(&mut commands, &asset_server, &game_fonts, &python_runtime);
-
-    commands.insert_resource(game_fonts);
+    spawn_game_objects(&mut commands, &asset_server, &python_runtime, &current_project.name);
 }
 
 // ゲームオブジェクト生成
-pub fn spawn_game_objects(commands: &mut Commands, asset_server: &AssetServer, game_fonts: &GameFonts, python_runtime: &PythonRuntime) {
+pub fn spawn_game_objects(commands: &mut Commands, asset_server: &AssetServer, python_runtime: &PythonRuntime, project_name: &str) {
     let mut player_state = default_player_state();
-    let mut enemy_state = default_enemy_state();
+    let mut lv_table = LvTable::default();
+    let mut enemies: Vec<EnemyState> = Vec::new();
     let mut menu_state = default_menu_state();
     let mut combat_state = default_combat_state();
 
-    let project_name = PROJECT_NAME;
     let mut item_dictionary = ItemDictionary::default();
-    let mut phase_script_name = String::new();
-
-    load_python_game_data(
-        python_runtime,
-        project_name,
-        &mut player_state,
-        &mut enemy_state,
-        &mut item_dictionary,
-        &mut phase_script_name,
-    );
-
-    validate_loaded_states(&mut player_state, &mut enemy_state);
-
-    apply_initial_phase(
-        project_name,
-        &phase_script_name,
-        python_runtime,
-        &mut enemy_state,
-        &mut combat_state,
-        &mut menu_state,
-    );
-
-    if !enemy_state.dialog_text.is_empty() {
+    let mut phase_scripts = PhaseScripts::default();
+
+    let manifest_result = project::load_project_manifest(python_runtime, project_name).and_then(|manifest| {
+        if !project_fs::is_mounted(project_name) {
+            return Ok(manifest);
+        }
+        // アーカイブ完全性
+        let missing = project_archive::check_project_complete(python_runtime, project_name);
+        if missing.is_empty() {
+            Ok(manifest)
+        } else {
+            Err(format!("archive {} incomplete: {}", project_name, missing.join(", ")))
+        }
+    });
+    let manifest = match &manifest_result {
+        Ok(manifest) => manifest.clone(),
+        Err(message) => {
+            println!("Error: {}", message);
+            ProjectManifest::default_layout(project_name)
+        }
+    };
+
+    if manifest_result.is_ok() {
+        load_python_game_data(
+            python_runtime,
+            project_name,
+            &manifest,
+            &mut player_state,
+            &mut lv_table,
+            &mut enemies,
+            &mut item_dictionary,
+        );
+    }
+
+    validate_loaded_states(&mut player_state, &mut enemies, &item_dictionary);
+
+    if manifest_result.is_ok() {
+        for enemy_state in enemies.iter_mut() {
+            apply_initial_phase(
+                project_name,
+                python_runtime,
+                enemy_state,
+                &mut combat_state,
+                &mut menu_state,
+                &mut phase_scripts,
+            );
+        }
+    }
+
+    if let Some(enemy_state) = enemies.iter().find(|enemy| !enemy.dialog_text.is_empty()) {
         menu_state.dialog_text = enemy_state.dialog_text.clone();
     }
+    if let Err(message) = &manifest_result {
+        menu_state.dialog_text = format!("* {}", message);
+    }
+
+    let damage_model = if manifest_result.is_ok() {
+        combat::load_damage_model(python_runtime, project_name, &manifest)
+    } else {
+        DamageModel::default()
+    };
+
+    let load_path = |path: &str| project::resolve_asset_path(project_name, &manifest, path);
+    let game_fonts = GameFonts {
+        main: asset_server.load(load_path(&manifest.font_main)),
+        dialog: asset_server.load(load_path(&manifest.font_dialog)),
+        hp_label: asset_server.load(load_path(&manifest.font_hp_label)),
+        damage: asset_server.load(load_path(&manifest.font_damage)),
+    };
 
-    spawn_enemy_entities(commands, asset_server, &enemy_state);
+    for (index, enemy_state) in enemies.iter().enumerate() {
+        spawn_enemy_entities(commands, asset_server, project_name, &manifest, index, enemy_state);
+    }
     spawn_soul(commands, asset_server);
     spawn_menu_buttons(commands, asset_server);
     spawn_battle_box_visuals(commands);
-    spawn_ui(commands, game_fonts, &player_state, &menu_state);
+    spawn_ui(commands, &game_fonts, &player_state, &menu_state);
+    spawn_music(commands, asset_server, project_name, &manifest);
 
+    commands.insert_resource(game_fonts);
+    commands.insert_resource(manifest);
     commands.insert_resource(item_dictionary);
     commands.insert_resource(player_state);
-    commands.insert_resource(enemy_state);
+    commands.insert_resource(lv_table);
+    commands.insert_resource(damage_model);
+    commands.insert_resource(Encounter { enemies, target: 0 });
     commands.insert_resource(menu_state);
     commands.insert_resource(combat_state);
+    commands.insert_resource(phase_scripts);
 }
 
 // プレイヤーデフォルト
@@ -98,6 +142,8 @@ fn default_player_state() -> PlayerState {
         hp: 0.0,
         max_hp: 0.0,
         lv: 1,
+        exp: 0,
+        gold: 0,
         name: String::new(),
         speed: 0.0,
         attack: 0.0,
@@ -110,12 +156,14 @@ fn default_player_state() -> PlayerState {
 }
 
 // 敵デフォルト
-fn default_enemy_state() -> EnemyState {
+pub fn default_enemy_state() -> EnemyState {
     EnemyState {
         hp: 0,
         max_hp: 0,
         atk: 0,
         def: 0,
+        exp: 0,
+        gold: 0,
         name: String::new(),
         dialog_text: String::new(),
         act_commands: vec![],
@@ -134,6 +182,16 @@ fn default_enemy_state() -> EnemyState {
         bubble_texture: "texture/blcon/spr_blconsm.png".to_string(),
         bubble_message_override: None,
         bubble_pos_override: None,
+        phase_name: String::new(),
+        phase_turn: 0,
+        phase_vars: HashMap::new(),
+        spareable: false,
+        spared: false,
+        fleeable: true,
+        spare_acts: HashMap::new(),
+        act_counts: HashMap::new(),
+        soul_mode: SoulMode::Red,
+        soul_gravity: GravityDirection::Down,
     }
 }
 
@@ -148,18 +206,23 @@ fn default_menu_state() -> MenuState {
 }
 
 // 戦闘デフォルト
-fn default_combat_state() -> CombatState {
+pub fn default_combat_state() -> CombatState {
     CombatState {
         mn_fight: MainFightState::Menu,
         my_fight: MessageFightState::None,
-        phase_name: String::new(),
-        phase_turn: 0,
         turn_count: 0,
         turn_timer: -1.0,
         bubble_timer: Timer::from_seconds(3.0, TimerMode::Once),
         damage_display_timer: Timer::from_seconds(1.0, TimerMode::Once),
         last_player_action: String::new(),
         last_act_command: None,
+        attacking_enemy: 0,
+        attack_bonus: 0.0,
+        defense_bonus: 0.0,
+        heal_over_time: vec![],
+        battle_result: None,
+        battle_finished: false,
+        battle_vars: HashMap::new(),
     }
 }
 
@@ -167,10 +230,11 @@ fn default_combat_state() -> CombatState {
 fn load_python_game_data(
     python_runtime: &PythonRuntime,
     project_name: &str,
+    manifest: &ProjectManifest,
     player_state: &mut PlayerState,
-    enemy_state: &mut EnemyState,
+    lv_table: &mut LvTable,
+    enemies: &mut Vec<EnemyState>,
     item_dictionary: &mut ItemDictionary,
-    phase_script_name: &mut String,
 ) {
     python_runtime.interpreter.enter(|vm| {
         let run_script = |code: &str, filename: &str| -> Option<Scope> {
@@ -189,15 +253,15 @@ fn load_python_game_data(
             Some(scope)
         };
 
-        let item_script = match python_scripts::get_item_script(project_name) {
+        let item_script = match python_scripts::get_project_script(project_name, &manifest.item_script) {
             Some(script) => script,
             None => {
-                println!("Warning: Could not load projects/{}/properties/item.py", project_name);
+                println!("Warning: Could not load projects/{}/{}", project_name, manifest.item_script);
                 String::new()
             }
         };
         if !item_script.is_empty() {
-            if let Some(scope) = run_script(&item_script, "item.py") {
+            if let Some(scope) = run_script(&item_script, &manifest.item_script) {
                 match scope.globals.get_item_opt("getItemData", vm) {
                     Ok(Some(func)) => match vm.invoke(&func, ()) {
                         Ok(result) => match result.try_into_value::<PyDictRef>(vm) {
@@ -219,12 +283,29 @@ fn load_python_game_data(
                                             continue;
                                         }
                                     };
-                                    let heal = read_option_i32(vm, &data, "heal", "itemData", true).unwrap_or(0);
-                                    let attack = read_option_i32(vm, &data, "attack", "itemData", true).unwrap_or(0);
-                                    let defense = read_option_i32(vm, &data, "defense", "itemData", true).unwrap_or(0);
+                                    let kind = match read_option_string(vm, &data, "kind", "itemData", false) {
+                                        Some(name) => ItemKind::from_name(&name).unwrap_or_else(|| {
+                                            println!("Warning: itemData {} kind {} invalid", item_name, name);
+                                            ItemKind::Consumable
+                                        }),
+                                        None => ItemKind::Consumable,
+                                    };
+                                    let heal = read_option_i32(vm, &data, "heal", "itemData", false).unwrap_or(0);
+                                    let attack = read_option_i32(vm, &data, "attack", "itemData", false).unwrap_or(0);
+                                    let defense = read_option_i32(vm, &data, "defense", "itemData", false).unwrap_or(0);
                                     let text = read_option_string(vm, &data, "text", "itemData", true).unwrap_or_default();
+                                    let bar_style = match read_option_string(vm, &data, "barStyle", "itemData", false) {
+                                        Some(name) => AttackBarStyle::from_name(&name).unwrap_or_else(|| {
+                                            println!("Warning: itemData {} barStyle {} invalid", item_name, name);
+                                            AttackBarStyle::Normal
+                                        }),
+                                        None => AttackBarStyle::Normal,
+                                    };
+                                    let damage_multiplier = read_option_f32(vm, &data, "damageMultiplier", "itemData", false).unwrap_or(1.0);
+                                    let consume = read_option_bool(vm, &data, "consume", "itemData", false).unwrap_or(true);
+                                    let use_callback = read_item_callback(vm, &scope, &data, &item_name);
 
-                                    item_dictionary.0.insert(item_name, ItemInfo { heal_amount: heal, attack, defense, text });
+                                    item_dictionary.0.insert(item_name, ItemInfo { kind, heal_amount: heal, attack, defense, text, bar_style, damage_multiplier, use_callback, consume });
                                 }
                             }
                             Err(err) => {
@@ -246,15 +327,15 @@ fn load_python_game_data(
             }
         }
 
-        let player_status_script = match python_scripts::get_player_status_script(project_name) {
+        let player_status_script = match python_scripts::get_project_script(project_name, &manifest.player_script) {
             Some(script) => script,
             None => {
-                println!("Warning: Could not load projects/{}/properties/playerStatus.py", project_name);
+                println!("Warning: Could not load projects/{}/{}", project_name, manifest.player_script);
                 String::new()
             }
         };
         if !player_status_script.is_empty() {
-            if let Some(scope) = run_script(&player_status_script, "playerStatus.py") {
+            if let Some(scope) = run_script(&player_status_script, &manifest.player_script) {
                 match scope.globals.get_item_opt("getPlayerStatus", vm) {
                     Ok(Some(func)) => match vm.invoke(&func, ()) {
                         Ok(result) => match result.try_into_value::<PyDictRef>(vm) {
@@ -262,22 +343,34 @@ fn load_python_game_data(
                                 if let Some(name) = read_option_string(vm, &dict, "name", "playerStatus", true) {
                                     player_state.name = name;
                                 }
-                                if let Some(lv) = read_option_i32(vm, &dict, "lv", "playerStatus", true) {
-                                    player_state.lv = lv;
+                                if let Some(levels) = read_lv_table(vm, &dict) {
+                                    lv_table.levels = levels;
+                                }
+                                let lv = read_option_i32(vm, &dict, "lv", "playerStatus", true).unwrap_or(player_state.lv);
+                                player_state.set_lv(lv, lv_table);
+                                if player_state.lv != lv {
+                                    println!("Warning: playerStatus lv {} out of lvTable", lv);
+                                }
+                                if let Some(exp) = read_option_i32(vm, &dict, "exp", "playerStatus", false) {
+                                    player_state.exp = exp;
                                 }
-                                if let Some(max_hp) = read_option_f32(vm, &dict, "maxHp", "playerStatus", true) {
+                                if let Some(gold) = read_option_i32(vm, &dict, "gold", "playerStatus", false) {
+                                    player_state.gold = gold;
+                                }
+                                if let Some(max_hp) = read_option_f32(vm, &dict, "maxHp", "playerStatus", false) {
                                     player_state.max_hp = max_hp;
+                                    player_state.hp = max_hp;
                                 }
-                                if let Some(hp) = read_option_f32(vm, &dict, "hp", "playerStatus", true) {
+                                if let Some(hp) = read_option_f32(vm, &dict, "hp", "playerStatus", false) {
                                     player_state.hp = hp;
                                 }
                                 if let Some(speed) = read_option_f32(vm, &dict, "speed", "playerStatus", true) {
                                     player_state.speed = speed;
                                 }
-                                if let Some(attack) = read_option_f32(vm, &dict, "attack", "playerStatus", true) {
+                                if let Some(attack) = read_option_f32(vm, &dict, "attack", "playerStatus", false) {
                                     player_state.attack = attack;
                                 }
-                                if let Some(defense) = read_option_f32(vm, &dict, "defense", "playerStatus", true) {
+                                if let Some(defense) = read_option_f32(vm, &dict, "defense", "playerStatus", false) {
                                     player_state.defense = defense;
                                 }
                                 if let Some(inv_dur) = read_option_f32(vm, &dict, "invincibilityDuration", "playerStatus", true) {
@@ -309,173 +402,321 @@ fn load_python_game_data(
             }
         }
 
-        let enemy_status_script = match python_scripts::get_enemy_status_script(project_name) {
-            Some(script) => script,
-            None => {
-                println!("Warning: Could not load projects/{}/properties/enemyStatus.py", project_name);
-                String::new()
+        for enemy_script in manifest.enemy_scripts() {
+            let enemy_status_script = match python_scripts::get_project_script(project_name, &enemy_script) {
+                Some(script) => script,
+                None => {
+                    println!("Warning: Could not load projects/{}/{}", project_name, enemy_script);
+                    continue;
+                }
+            };
+            let scope = match run_script(&enemy_status_script, &enemy_script) {
+                Some(scope) => scope,
+                None => continue,
+            };
+            let result = match scope.globals.get_item_opt("getEnemyStatus", vm) {
+                Ok(Some(func)) => match func.call((), vm) {
+                    Ok(result) => result,
+                    Err(err) => {
+                        vm.print_exception(err.clone());
+                        println!("Warning: enemyStatus call {:?}", err);
+                        continue;
+                    }
+                },
+                Ok(None) => {
+                    println!("Warning: enemyStatus missing getEnemyStatus");
+                    continue;
+                }
+                Err(err) => {
+                    vm.print_exception(err.clone());
+                    println!("Warning: enemyStatus lookup {:?}", err);
+                    continue;
+                }
+            };
+            // 単体または一覧
+            let dicts = match result.clone().try_into_value::<PyDictRef>(vm) {
+                Ok(dict) => vec![dict],
+                Err(_) => match result.try_into_value::<Vec<PyDictRef>>(vm) {
+                    Ok(dicts) => dicts,
+                    Err(err) => {
+                        vm.print_exception(err.clone());
+                        println!("Warning: enemyStatus result {:?}", err);
+                        continue;
+                    }
+                },
+            };
+            for dict in dicts {
+                if enemies.len() >= MAX_ENEMIES {
+                    println!("Warning: encounter exceeds {} enemies", MAX_ENEMIES);
+                    break;
+                }
+                let mut enemy_state = default_enemy_state();
+                read_enemy_status(vm, &dict, &mut enemy_state);
+                enemies.push(enemy_state);
             }
-        };
-        if !enemy_status_script.is_empty() {
-            if let Some(scope) = run_script(&enemy_status_script, "enemyStatus.py") {
-                match scope.globals.get_item_opt("getEnemyStatus", vm) {
-                    Ok(Some(func)) => match vm.invoke(&func, ()) {
-                        Ok(result) => match result.try_into_value::<PyDictRef>(vm) {
-                            Ok(dict) => {
-                                if let Some(hp) = read_option_i32(vm, &dict, "enemyHp", "enemyStatus", true) {
-                                    enemy_state.hp = hp;
-                                }
-                                if let Some(max_hp) = read_option_i32(vm, &dict, "enemyMaxHp", "enemyStatus", true) {
-                                    enemy_state.max_hp = max_hp;
-                                }
-                                if let Some(atk) = read_option_i32(vm, &dict, "enemyAtk", "enemyStatus", true) {
-                                    enemy_state.atk = atk;
-                                }
-                                if let Some(def) = read_option_i32(vm, &dict, "enemyDef", "enemyStatus", true) {
-                                    enemy_state.def = def;
-                                }
-                                if let Some(name) = read_option_string(vm, &dict, "enemyName", "enemyStatus", true) {
-                                    enemy_state.name = name;
-                                }
-                                if let Some(dialog_text) = read_option_string(vm, &dict, "dialogText", "enemyStatus", true) {
-                                    enemy_state.dialog_text = dialog_text;
-                                }
-                                if let Some(phase_script) = read_option_string(vm, &dict, "phaseScript", "enemyStatus", true) {
-                                    *phase_script_name = phase_script;
-                                }
-                                if let Some(attacks) = read_option_vec_string(vm, &dict, "attackPatterns", "enemyStatus", true) {
-                                    enemy_state.attacks = attacks;
-                                }
-                                if let Some(commands) = read_option_vec_string(vm, &dict, "actCommands", "enemyStatus", true) {
-                                    enemy_state.act_commands = commands;
-                                }
-                                match dict.get_item_opt("actTexts", vm) {
-                                    Ok(Some(act_texts_obj)) => match act_texts_obj.try_into_value::<PyDictRef>(vm) {
-                                        Ok(act_texts) => {
-                                            for (key, value) in &act_texts {
-                                                let command: String = match key.try_into_value(vm) {
-                                                    Ok(name) => name,
-                                                    Err(err) => {
-                                                        vm.print_exception(err.clone());
-                                                        println!("Warning: enemyStatus actTexts key {:?}", err);
-                                                        continue;
-                                                    }
-                                                };
-                                                let text: String = match value.try_into_value(vm) {
-                                                    Ok(result) => result,
-                                                    Err(err) => {
-                                                        vm.print_exception(err.clone());
-                                                        println!("Warning: enemyStatus actTexts value {:?}", err);
-                                                        continue;
-                                                    }
-                                                };
-                                                enemy_state.act_texts.insert(command, text);
-                                            }
+        }
+
+        for enemy_state in enemies.iter_mut() {
+            if enemy_state.phase_name.is_empty() && !manifest.initial_phase.is_empty() {
+                enemy_state.phase_name = manifest.initial_phase.clone();
+            }
+            if !enemy_state.tachie_script.is_empty() {
+                let tachie_script = match python_scripts::get_tachie_script(project_name, &enemy_state.tachie_script) {
+                    Some(script) => script,
+                    None => {
+                        println!(
+                            "Warning: Could not load projects/{}/tachie/{}.py",
+                            project_name, enemy_state.tachie_script
+                        );
+                        String::new()
+                    }
+                };
+                if !tachie_script.is_empty() {
+                    let filename = format!("tachie/{}.py", enemy_state.tachie_script);
+                    if let Some(scope) = run_script(&tachie_script, &filename) {
+                        match scope.globals.get_item_opt("getTachieData", vm) {
+                            Ok(Some(func)) => match vm.invoke(&func, ()) {
+                                Ok(result) => match result.try_into_value::<PyDictRef>(vm) {
+                                    Ok(dict) => {
+                                        if let Some(speed) = read_option_f32(vm, &dict, "headSwaySpeed", "tachie", true) {
+                                            enemy_state.head_sway_speed = speed;
                                         }
-                                        Err(err) => {
-                                            vm.print_exception(err.clone());
-                                            println!("Warning: enemyStatus actTexts {:?}", err);
+                                        if let Some(amplitude) = read_option_f32(vm, &dict, "headSwayAmplitude", "tachie", true) {
+                                            enemy_state.head_sway_amplitude = amplitude;
                                         }
-                                    },
-                                    Ok(None) => println!("Warning: enemyStatus missing actTexts"),
+                                    }
                                     Err(err) => {
                                         vm.print_exception(err.clone());
-                                        println!("Warning: enemyStatus actTexts {:?}", err);
+                                        println!("Warning: tachie result {:?}", err);
                                     }
+                                },
+                                Err(err) => {
+                                    vm.print_exception(err.clone());
+                                    println!("Warning: tachie call {:?}", err);
                                 }
-                                if let Some(messages) = read_option_vec_string(vm, &dict, "bubbleMessages", "enemyStatus", true) {
-                                    enemy_state.bubble_messages = messages;
-                                }
-                                if let Some(body_texture) = read_option_string(vm, &dict, "bodyTexture", "enemyStatus", true) {
-                                    enemy_state.body_texture = body_texture;
-                                }
-                                if let Some(head_texture) = read_option_string(vm, &dict, "headTexture", "enemyStatus", true) {
-                                    enemy_state.head_texture = head_texture;
-                                }
-                                if let Some(head_yoffset) = read_option_f32(vm, &dict, "headYOffset", "enemyStatus", true) {
-                                    enemy_state.head_yoffset = head_yoffset;
-                                }
-                                if let Some(tachie_script) = read_option_string(vm, &dict, "tachieScript", "enemyStatus", true) {
-                                    enemy_state.tachie_script = tachie_script;
-                                }
-                                if let Some(base_x) = read_option_f32(vm, &dict, "baseX", "enemyStatus", true) {
-                                    enemy_state.base_x = base_x;
-                                }
-                                if let Some(base_y) = read_option_f32(vm, &dict, "baseY", "enemyStatus", true) {
-                                    enemy_state.base_y = base_y;
-                                }
-                                if let Some(scale) = read_option_f32(vm, &dict, "scale", "enemyStatus", true) {
-                                    enemy_state.scale = scale;
-                                }
-                            }
+                            },
+                            Ok(None) => println!("Warning: tachie missing getTachieData"),
                             Err(err) => {
                                 vm.print_exception(err.clone());
-                                println!("Warning: enemyStatus result {:?}", err);
+                                println!("Warning: tachie lookup {:?}", err);
                             }
-                        },
-                        Err(err) => {
-                            vm.print_exception(err.clone());
-                            println!("Warning: enemyStatus call {:?}", err);
                         }
-                    },
-                    Ok(None) => println!("Warning: enemyStatus missing getEnemyStatus"),
-                    Err(err) => {
-                        vm.print_exception(err.clone());
-                        println!("Warning: enemyStatus lookup {:?}", err);
                     }
                 }
             }
         }
+    });
+}
+
+// アイテム使用コールバック
+fn read_item_callback(vm: &VirtualMachine, scope: &Scope, data: &PyDictRef, item_name: &str) -> Option<PyObjectRef> {
+    let value = match data.get_item_opt("use", vm) {
+        Ok(Some(value)) => value,
+        Ok(None) => return None,
+        Err(err) => {
+            vm.print_exception(err.clone());
+            return None;
+        }
+    };
+    // 関数名指定はitem.pyから解決
+    let callback = match value.clone().try_into_value::<String>(vm) {
+        Ok(name) => match scope.globals.get_item_opt(name.as_str(), vm) {
+            Ok(Some(func)) => func,
+            _ => {
+                println!("Warning: itemData {} use {} missing", item_name, name);
+                return None;
+            }
+        },
+        Err(_) => value,
+    };
+    if !callback.is_callable() {
+        println!("Warning: itemData {} use is not callable", item_name);
+        return None;
+    }
+    Some(callback)
+}
+
+// LV表読込
+fn read_lv_table(vm: &VirtualMachine, dict: &PyDictRef) -> Option<Vec<LvStats>> {
+    let value = match dict.get_item_opt("lvTable", vm) {
+        Ok(Some(value)) => value,
+        Ok(None) => return None,
+        Err(err) => {
+            vm.print_exception(err.clone());
+            println!("Warning: playerStatus lvTable {:?}", err);
+            return None;
+        }
+    };
+    let entries = match value.try_into_value::<Vec<PyDictRef>>(vm) {
+        Ok(entries) => entries,
+        Err(err) => {
+            vm.print_exception(err.clone());
+            println!("Warning: playerStatus lvTable {:?}", err);
+            return None;
+        }
+    };
+    if entries.is_empty() {
+        println!("Warning: playerStatus lvTable empty");
+        return None;
+    }
+    // intも数値として扱う
+    let number = |entry: &PyDictRef, key: &str, default: f32| match entry.get_item_opt(key, vm) {
+        Ok(Some(value)) => py_to_f32(vm, &value).unwrap_or_else(|| {
+            println!("Warning: lvTable {} must be number", key);
+            default
+        }),
+        _ => {
+            println!("Warning: lvTable missing {}", key);
+            default
+        }
+    };
+    let levels = entries.iter().map(|entry| LvStats {
+        exp: read_option_i32(vm, entry, "exp", "lvTable", true).unwrap_or(0),
+        max_hp: number(entry, "maxHp", 20.0),
+        attack: number(entry, "attack", 0.0),
+        defense: number(entry, "defense", 0.0),
+    }).collect();
+    Some(levels)
+}
 
-        if !enemy_state.tachie_script.is_empty() {
-            let tachie_script = match python_scripts::get_tachie_script(project_name, &enemy_state.tachie_script) {
-                Some(script) => script,
-                None => {
-                    println!(
-                        "Warning: Could not load projects/{}/tachie/{}.py",
-                        project_name, enemy_state.tachie_script
-                    );
-                    String::new()
+// 敵ステータス適用
+fn read_enemy_status(vm: &VirtualMachine, dict: &PyDictRef, enemy_state: &mut EnemyState) {
+    if let Some(hp) = read_option_i32(vm, dict, "enemyHp", "enemyStatus", true) {
+        enemy_state.hp = hp;
+    }
+    if let Some(max_hp) = read_option_i32(vm, dict, "enemyMaxHp", "enemyStatus", true) {
+        enemy_state.max_hp = max_hp;
+    }
+    if let Some(atk) = read_option_i32(vm, dict, "enemyAtk", "enemyStatus", true) {
+        enemy_state.atk = atk;
+    }
+    if let Some(def) = read_option_i32(vm, dict, "enemyDef", "enemyStatus", true) {
+        enemy_state.def = def;
+    }
+    if let Some(exp) = read_option_i32(vm, dict, "enemyExp", "enemyStatus", false) {
+        enemy_state.exp = exp;
+    }
+    if let Some(gold) = read_option_i32(vm, dict, "enemyGold", "enemyStatus", false) {
+        enemy_state.gold = gold;
+    }
+    if let Some(name) = read_option_string(vm, dict, "enemyName", "enemyStatus", true) {
+        enemy_state.name = name;
+    }
+    if let Some(dialog_text) = read_option_string(vm, dict, "dialogText", "enemyStatus", true) {
+        enemy_state.dialog_text = dialog_text;
+    }
+    if let Some(phase_script) = read_option_string(vm, dict, "phaseScript", "enemyStatus", true) {
+        enemy_state.phase_name = phase_script;
+    }
+    if let Some(attacks) = read_option_vec_string(vm, dict, "attackPatterns", "enemyStatus", true) {
+        enemy_state.attacks = attacks;
+    }
+    if let Some(commands) = read_option_vec_string(vm, dict, "actCommands", "enemyStatus", true) {
+        enemy_state.act_commands = commands;
+    }
+    match dict.get_item_opt("actTexts", vm) {
+        Ok(Some(act_texts_obj)) => match act_texts_obj.try_into_value::<PyDictRef>(vm) {
+            Ok(act_texts) => {
+                for (key, value) in &act_texts {
+                    let command: String = match key.try_into_value(vm) {
+                        Ok(name) => name,
+                        Err(err) => {
+                            vm.print_exception(err.clone());
+                            println!("Warning: enemyStatus actTexts key {:?}", err);
+                            continue;
+                        }
+                    };
+                    let text: String = match value.try_into_value(vm) {
+                        Ok(result) => result,
+                        Err(err) => {
+                            vm.print_exception(err.clone());
+                            println!("Warning: enemyStatus actTexts value {:?}", err);
+                            continue;
+                        }
+                    };
+                    enemy_state.act_texts.insert(command, text);
                 }
-            };
-            if !tachie_script.is_empty() {
-                let filename = format!("tachie/{}.py", enemy_state.tachie_script);
-                if let Some(scope) = run_script(&tachie_script, &filename) {
-                    match scope.globals.get_item_opt("getTachieData", vm) {
-                        Ok(Some(func)) => match vm.invoke(&func, ()) {
-                            Ok(result) => match result.try_into_value::<PyDictRef>(vm) {
-                                Ok(dict) => {
-                                    if let Some(speed) = read_option_f32(vm, &dict, "headSwaySpeed", "tachie", true) {
-                                        enemy_state.head_sway_speed = speed;
-                                    }
-                                    if let Some(amplitude) = read_option_f32(vm, &dict, "headSwayAmplitude", "tachie", true) {
-                                        enemy_state.head_sway_amplitude = amplitude;
-                                    }
-                                }
-                                Err(err) => {
-                                    vm.print_exception(err.clone());
-                                    println!("Warning: tachie result {:?}", err);
-                                }
-                            },
-                            Err(err) => {
-                                vm.print_exception(err.clone());
-                                println!("Warning: tachie call {:?}", err);
-                            }
-                        },
-                        Ok(None) => println!("Warning: tachie missing getTachieData"),
+            }
+            Err(err) => {
+                vm.print_exception(err.clone());
+                println!("Warning: enemyStatus actTexts {:?}", err);
+            }
+        },
+        Ok(None) => println!("Warning: enemyStatus missing actTexts"),
+        Err(err) => {
+            vm.print_exception(err.clone());
+            println!("Warning: enemyStatus actTexts {:?}", err);
+        }
+    }
+    if let Some(messages) = read_option_vec_string(vm, dict, "bubbleMessages", "enemyStatus", true) {
+        enemy_state.bubble_messages = messages;
+    }
+    if let Some(body_texture) = read_option_string(vm, dict, "bodyTexture", "enemyStatus", true) {
+        enemy_state.body_texture = body_texture;
+    }
+    if let Some(head_texture) = read_option_string(vm, dict, "headTexture", "enemyStatus", true) {
+        enemy_state.head_texture = head_texture;
+    }
+    if let Some(head_yoffset) = read_option_f32(vm, dict, "headYOffset", "enemyStatus", true) {
+        enemy_state.head_yoffset = head_yoffset;
+    }
+    if let Some(tachie_script) = read_option_string(vm, dict, "tachieScript", "enemyStatus", true) {
+        enemy_state.tachie_script = tachie_script;
+    }
+    if let Some(base_x) = read_option_f32(vm, dict, "baseX", "enemyStatus", true) {
+        enemy_state.base_x = base_x;
+    }
+    if let Some(base_y) = read_option_f32(vm, dict, "baseY", "enemyStatus", true) {
+        enemy_state.base_y = base_y;
+    }
+    if let Some(scale) = read_option_f32(vm, dict, "scale", "enemyStatus", true) {
+        enemy_state.scale = scale;
+    }
+    if let Some(spareable) = read_option_bool(vm, dict, "spareable", "enemyStatus", false) {
+        enemy_state.spareable = spareable;
+    }
+    if let Some(fleeable) = read_option_bool(vm, dict, "fleeable", "enemyStatus", false) {
+        enemy_state.fleeable = fleeable;
+    }
+    // ACT回数による見逃し条件
+    match dict.get_item_opt("spareActs", vm) {
+        Ok(Some(spare_acts_obj)) => match spare_acts_obj.try_into_value::<PyDictRef>(vm) {
+            Ok(spare_acts) => {
+                for (key, value) in &spare_acts {
+                    let command: String = match key.try_into_value(vm) {
+                        Ok(name) => name,
                         Err(err) => {
                             vm.print_exception(err.clone());
-                            println!("Warning: tachie lookup {:?}", err);
+                            println!("Warning: enemyStatus spareActs key {:?}", err);
+                            continue;
                         }
-                    }
+                    };
+                    let count: i32 = match value.try_into_value(vm) {
+                        Ok(count) => count,
+                        Err(err) => {
+                            vm.print_exception(err.clone());
+                            println!("Warning: enemyStatus spareActs value {:?}", err);
+                            continue;
+                        }
+                    };
+                    enemy_state.spare_acts.insert(command, count.max(1));
                 }
             }
+            Err(err) => {
+                vm.print_exception(err.clone());
+                println!("Warning: enemyStatus spareActs {:?}", err);
+            }
+        },
+        Ok(None) => {}
+        Err(err) => {
+            vm.print_exception(err.clone());
+            println!("Warning: enemyStatus spareActs {:?}", err);
         }
-    });
+    }
 }
 
 // 読み込み検証
-fn validate_loaded_states(player_state: &mut PlayerState, enemy_state: &mut EnemyState) {
+fn validate_loaded_states(player_state: &mut PlayerState, enemies: &mut Vec<EnemyState>, item_dictionary: &ItemDictionary) {
     if player_state.name.is_empty() {
         println!("Warning: playerStatus missing name");
     }
@@ -498,47 +739,73 @@ fn validate_loaded_states(player_state: &mut PlayerState, enemy_state: &mut Enem
         println!("Warning: playerStatus invincibilityDuration invalid");
     }
 
-    if enemy_state.max_hp <= 0 {
-        println!("Warning: enemyMaxHp invalid");
-        enemy_state.max_hp = 1;
-    }
+    // 装備確認(種別ごとに1つ)
+    let mut equipped_kinds = Vec::new();
+    player_state.equipped_items.retain(|item_name| {
+        let Some(info) = item_dictionary.0.get(item_name) else {
+            println!("Warning: playerStatus equippedItems {} missing in ItemDictionary", item_name);
+            return false;
+        };
+        if !info.kind.is_equipment() {
+            println!("Warning: playerStatus equippedItems {} is not weapon or armor", item_name);
+            return false;
+        }
+        if equipped_kinds.contains(&info.kind) {
+            println!("Warning: playerStatus equippedItems {} duplicates {:?}", item_name, info.kind);
+            return false;
+        }
+        equipped_kinds.push(info.kind);
+        true
+    });
 
-    if enemy_state.name.is_empty() {
-        println!("Warning: enemyStatus missing enemyName");
+    if enemies.is_empty() {
+        println!("Warning: encounter has no enemies");
+        enemies.push(default_enemy_state());
     }
 
-    if enemy_state.body_texture.is_empty() {
-        println!("Warning: enemyStatus missing bodyTexture");
-    }
+    for enemy_state in enemies.iter_mut() {
+        if enemy_state.max_hp <= 0 {
+            println!("Warning: enemyMaxHp invalid");
+            enemy_state.max_hp = 1;
+        }
 
-    if enemy_state.head_texture.is_empty() {
-        println!("Warning: enemyStatus missing headTexture");
+        if enemy_state.name.is_empty() {
+            println!("Warning: enemyStatus missing enemyName");
+        }
+
+        if enemy_state.body_texture.is_empty() {
+            println!("Warning: enemyStatus missing bodyTexture");
+        }
+
+        if enemy_state.head_texture.is_empty() {
+            println!("Warning: enemyStatus missing headTexture");
+        }
     }
 }
 
 // 初期フェーズ適用
 fn apply_initial_phase(
     project_name: &str,
-    phase_script_name: &str,
     python_runtime: &PythonRuntime,
     enemy_state: &mut EnemyState,
     combat_state: &mut CombatState,
     menu_state: &mut MenuState,
+    phase_scripts: &mut PhaseScripts,
 ) {
-    combat_state.phase_name = phase::resolve_initial_phase(project_name, phase_script_name, python_runtime);
-    if !combat_state.phase_name.is_empty() {
-        if let Some(next_phase) = phase::apply_phase_update(enemy_state, combat_state, menu_state, project_name, "start", python_runtime) {
-            if next_phase != combat_state.phase_name {
-                combat_state.phase_name = next_phase;
-                combat_state.phase_turn = 0;
-                let _ = phase::apply_phase_update(enemy_state, combat_state, menu_state, project_name, "start", python_runtime);
+    enemy_state.phase_name = phase::resolve_initial_phase(project_name, &enemy_state.phase_name, python_runtime);
+    if !enemy_state.phase_name.is_empty() {
+        if let Some(next_phase) = phase::apply_phase_update(enemy_state, combat_state, menu_state, phase_scripts, project_name, "start", python_runtime) {
+            if next_phase != enemy_state.phase_name {
+                enemy_state.phase_name = next_phase;
+                enemy_state.phase_turn = 0;
+                let _ = phase::apply_phase_update(enemy_state, combat_state, menu_state, phase_scripts, project_name, "start", python_runtime);
             }
         }
     }
 }
 
 // 敵生成
-fn spawn_enemy_entities(commands: &mut Commands, asset_server: &AssetServer, enemy_state: &EnemyState) {
+fn spawn_enemy_entities(commands: &mut Commands, asset_server: &AssetServer, project_name: &str, manifest: &ProjectManifest, index: usize, enemy_state: &EnemyState) {
     let enemy_base_x = enemy_state.base_x;
     let enemy_base_y = enemy_state.base_y;
     let enemy_scale = if enemy_state.scale <= 0.0 {
@@ -550,7 +817,7 @@ fn spawn_enemy_entities(commands: &mut Commands, asset_server: &AssetServer, ene
 
     commands.spawn((
         SpriteBundle {
-            sprite: Sprite { image: asset_server.load(&enemy_state.body_texture), color: Color::WHITE, custom_size: None, ..default() },
+            sprite: Sprite { image: asset_server.load(project::resolve_asset_path(project_name, manifest, &enemy_state.body_texture)), color: Color::WHITE, custom_size: None, ..default() },
             transform: Transform {
                 translation: gml_to_bevy(enemy_base_x, enemy_base_y) + Vec3::new(0.0, 0.0, Z_ENEMY_BODY),
                 scale: Vec3::splat(enemy_scale),
@@ -558,10 +825,7 @@ fn spawn_enemy_entities(commands: &mut Commands, asset_server: &AssetServer, ene
             },
             ..default()
         },
-        EnemyBody,
-        ActCommands {
-            commands: enemy_state.act_commands.clone(),
-        },
+        EnemyBody { index },
         Cleanup,
     ));
 
@@ -569,7 +833,7 @@ fn spawn_enemy_entities(commands: &mut Commands, asset_server: &AssetServer, ene
     let head_pos = gml_to_bevy(enemy_base_x, enemy_base_y - head_yoffset);
     commands.spawn((
         SpriteBundle {
-            sprite: Sprite { image: asset_server.load(&enemy_state.head_texture), color: Color::WHITE, custom_size: None, ..default() },
+            sprite: Sprite { image: asset_server.load(project::resolve_asset_path(project_name, manifest, &enemy_state.head_texture)), color: Color::WHITE, custom_size: None, ..default() },
             transform: Transform {
                 translation: head_pos + Vec3::new(0.0, 0.0, Z_ENEMY_HEAD),
                 scale: Vec3::splat(enemy_scale),
@@ -583,11 +847,22 @@ fn spawn_enemy_entities(commands: &mut Commands, asset_server: &AssetServer, ene
             sway_speed: enemy_state.head_sway_speed,
             sway_amplitude: enemy_state.head_sway_amplitude,
         },
-        EnemyBody,
+        EnemyBody { index },
         Cleanup,
     ));
 }
 
+// BGM再生
+fn spawn_music(commands: &mut Commands, asset_server: &AssetServer, project_name: &str, manifest: &ProjectManifest) {
+    if let Some(music) = &manifest.music {
+        commands.spawn((
+            AudioPlayer::new(asset_server.load(project::resolve_asset_path(project_name, manifest, music))),
+            PlaybackSettings::LOOP,
+            Cleanup,
+        ));
+    }
+}
+
 // ソウル生成
 fn spawn_soul(commands: &mut Commands, asset_server: &AssetServer) {
     commands.spawn((
@@ -597,6 +872,7 @@ fn spawn_soul(commands: &mut Commands, asset_server: &AssetServer) {
             ..default()
         },
         Soul,
+        SoulMotion::default(),
         Cleanup,
     ));
 }
@@ -756,3 +1032,26 @@ pub fn camera_scaling_system(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::python_utils::eval_test_expr;
+
+    #[test]
+    fn read_lv_table_accepts_ints() {
+        let levels = eval_test_expr("{'lvTable': [{'exp': 0, 'maxHp': 20, 'attack': 20, 'defense': 0}, {'exp': 10, 'maxHp': 24.5, 'attack': 22, 'defense': 1}]}", |vm, value| {
+            read_lv_table(vm, &value.try_into_value(vm).unwrap())
+        }).unwrap();
+        assert_eq!(levels, vec![
+            LvStats { exp: 0, max_hp: 20.0, attack: 20.0, defense: 0.0 },
+            LvStats { exp: 10, max_hp: 24.5, attack: 22.0, defense: 1.0 },
+        ]);
+    }
+
+    #[test]
+    fn read_lv_table_falls_back_when_missing_or_empty() {
+        assert_eq!(eval_test_expr("{}", |vm, value| read_lv_table(vm, &value.try_into_value(vm).unwrap())), None);
+        assert_eq!(eval_test_expr("{'lvTable': []}", |vm, value| read_lv_table(vm, &value.try_into_value(vm).unwrap())), None);
+    }
+}
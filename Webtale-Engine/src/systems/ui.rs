@@ -8,13 +8,12 @@ use crate::constants::*;
 pub fn menu_render_system(
     mut commands: Commands,
     combat_state: Res<CombatState>,
-    enemy_state: Res<EnemyState>,
+    encounter: Res<Encounter>,
     menu_state: Res<MenuState>,
     player_state: Res<PlayerState>,
     game_fonts: Res<GameFonts>,
     menu_items: Query<Entity, With<MenuTextItem>>,
     typewriter_query: Query<Entity, With<MainDialogText>>,
-    act_commands_query: Query<&ActCommands, With<EnemyBody>>,
     mut menu_render_cache: ResMut<MenuRenderCache>,
 ){
     let is_menu = combat_state.mn_fight == MainFightState::Menu && combat_state.my_fight == MessageFightState::None;
@@ -29,20 +28,24 @@ pub fn menu_render_system(
     }
 
     // メニュー差分
-    let act_commands = act_commands_query
-        .iter()
-        .next()
-        .map(|acts| acts.commands.clone())
+    let active_enemies = encounter.active_indices();
+    let act_commands = encounter
+        .target_enemy()
+        .map(|enemy| enemy.act_commands.clone())
         .unwrap_or_default();
     let key = MenuRenderKey {
         menu_layer: menu_state.menu_layer,
         menu_coords: menu_state.menu_coords.clone(),
         item_page: menu_state.item_page,
         dialog_text: menu_state.dialog_text.clone(),
-        enemy_name: enemy_state.name.clone(),
-        enemy_hp: enemy_state.hp,
-        enemy_max_hp: enemy_state.max_hp,
-        act_commands,
+        enemies: active_enemies
+            .iter()
+            .map(|&index| {
+                let enemy = &encounter.enemies[index];
+                (enemy.name.clone(), enemy.hp, enemy.max_hp, enemy.spareable)
+            })
+            .collect(),
+        act_commands: act_commands.clone(),
         inventory: player_state.inventory.clone(),
     };
 
@@ -92,63 +95,67 @@ pub fn menu_render_system(
         let start_y = 270.0;
 
         if layer == MENU_LAYER_FIGHT_TARGET || layer == MENU_LAYER_ACT_TARGET {
-            let enemy_name = if enemy_state.name.is_empty() { "Enemy" } else { &enemy_state.name };
-            commands.spawn((
-                Text2d::new(format!("* {}", enemy_name)),
-                menu_font.clone(),
-                menu_color,
-                Anchor::TopLeft,
-                Transform::from_translation(gml_to_bevy(start_x, start_y) + Vec3::new(0.0, 0.0, Z_TEXT)),
-                MenuTextItem { layer, index: 0 },
-                Cleanup,
-            ));
-
-            if layer == MENU_LAYER_FIGHT_TARGET {
-                let bar_width = 100.0;
-                let bar_height = 20.0;
-                let bar_x = start_x + 220.0;
-                let bar_y = start_y + 5.0;
-
+            for (row, &index) in active_enemies.iter().enumerate() {
+                let enemy_state = &encounter.enemies[index];
+                let enemy_name = if enemy_state.name.is_empty() { "Enemy" } else { &enemy_state.name };
+                let row_y = start_y + (row as f32) * 32.0;
+                // 見逃し可能なら黄色
+                let name_color = if enemy_state.spareable { TextColor(Color::srgb(1.0, 1.0, 0.0)) } else { menu_color };
                 commands.spawn((
-                    SpriteBundle {
-                        sprite: Sprite { color: Color::rgb(1.0, 0.0, 0.0), custom_size: Some(Vec2::new(bar_width, bar_height)), anchor: Anchor::TopLeft, ..default() },
-                        transform: Transform::from_translation(gml_to_bevy(bar_x, bar_y) + Vec3::new(0.0, 0.0, Z_TEXT)),
-                        ..default()
-                    },
-                    MenuTextItem { layer, index: 0 },
+                    Text2d::new(format!("* {}", enemy_name)),
+                    menu_font.clone(),
+                    name_color,
+                    Anchor::TopLeft,
+                    Transform::from_translation(gml_to_bevy(start_x, row_y) + Vec3::new(0.0, 0.0, Z_TEXT)),
+                    MenuTextItem { layer, index: row as i32 },
                     Cleanup,
                 ));
 
-                let hp_percent = (enemy_state.hp as f32 / enemy_state.max_hp as f32).max(0.0);
-                commands.spawn((
-                    SpriteBundle {
-                        sprite: Sprite { color: Color::rgb(0.0, 1.0, 0.0), custom_size: Some(Vec2::new(bar_width * hp_percent, bar_height)), anchor: Anchor::TopLeft, ..default() },
-                        transform: Transform::from_translation(gml_to_bevy(bar_x, bar_y) + Vec3::new(0.0, 0.0, Z_TEXT + 0.1)),
-                        ..default()
-                    },
-                    MenuTextItem { layer, index: 0 },
-                    Cleanup,
-                ));
-            }
+                if layer == MENU_LAYER_FIGHT_TARGET {
+                    let bar_width = 100.0;
+                    let bar_height = 20.0;
+                    let bar_x = start_x + 220.0;
+                    let bar_y = row_y + 5.0;
 
-        } else if layer == MENU_LAYER_ACT_COMMAND {
-            if let Some(acts) = act_commands_query.iter().next() {
-                for (i, cmd_name) in acts.commands.iter().enumerate() {
-                    let col = i % 2;
-                    let row = i / 2;
-                    let x_offset = if col == 0 { 0.0 } else { 240.0 };
-                    let y_offset = (row as f32) * 32.0;
                     commands.spawn((
-                        Text2d::new(format!("* {}", cmd_name)),
-                        menu_font.clone(),
-                        menu_color,
-                        Anchor::TopLeft,
-                        Transform::from_translation(gml_to_bevy(start_x + x_offset, start_y + y_offset) + Vec3::new(0.0, 0.0, Z_TEXT)),
-                        MenuTextItem { layer, index: i as i32 },
+                        SpriteBundle {
+                            sprite: Sprite { color: Color::rgb(1.0, 0.0, 0.0), custom_size: Some(Vec2::new(bar_width, bar_height)), anchor: Anchor::TopLeft, ..default() },
+                            transform: Transform::from_translation(gml_to_bevy(bar_x, bar_y) + Vec3::new(0.0, 0.0, Z_TEXT)),
+                            ..default()
+                        },
+                        MenuTextItem { layer, index: row as i32 },
+                        Cleanup,
+                    ));
+
+                    let hp_percent = (enemy_state.hp as f32 / enemy_state.max_hp as f32).max(0.0);
+                    commands.spawn((
+                        SpriteBundle {
+                            sprite: Sprite { color: Color::rgb(0.0, 1.0, 0.0), custom_size: Some(Vec2::new(bar_width * hp_percent, bar_height)), anchor: Anchor::TopLeft, ..default() },
+                            transform: Transform::from_translation(gml_to_bevy(bar_x, bar_y) + Vec3::new(0.0, 0.0, Z_TEXT + 0.1)),
+                            ..default()
+                        },
+                        MenuTextItem { layer, index: row as i32 },
                         Cleanup,
                     ));
                 }
             }
+
+        } else if layer == MENU_LAYER_ACT_COMMAND {
+            for (i, cmd_name) in act_commands.iter().enumerate() {
+                let col = i % 2;
+                let row = i / 2;
+                let x_offset = if col == 0 { 0.0 } else { 240.0 };
+                let y_offset = (row as f32) * 32.0;
+                commands.spawn((
+                    Text2d::new(format!("* {}", cmd_name)),
+                    menu_font.clone(),
+                    menu_color,
+                    Anchor::TopLeft,
+                    Transform::from_translation(gml_to_bevy(start_x + x_offset, start_y + y_offset) + Vec3::new(0.0, 0.0, Z_TEXT)),
+                    MenuTextItem { layer, index: i as i32 },
+                    Cleanup,
+                ));
+            }
         } else if layer == MENU_LAYER_ITEM {
             let page_start = menu_state.item_page * ITEMS_PER_PAGE;
             for i in 0..ITEMS_PER_PAGE {
@@ -183,12 +190,14 @@ pub fn menu_render_system(
             ));
 
         } else if layer == MENU_LAYER_MERCY {
-            let options = ["* Spare", "* Flee"];
+            let options: &[&str] = if encounter.can_flee() { &["* Spare", "* Flee"] } else { &["* Spare"] };
+            let can_spare = active_enemies.iter().any(|&index| encounter.enemies[index].spareable);
             for (i, opt) in options.iter().enumerate() {
+                let option_color = if i == 0 && can_spare { TextColor(Color::srgb(1.0, 1.0, 0.0)) } else { menu_color };
                 commands.spawn((
                     Text2d::new(*opt),
                     menu_font.clone(),
-                    menu_color,
+                    option_color,
                     Anchor::TopLeft,
                     Transform::from_translation(gml_to_bevy(start_x, start_y + (i as f32 * 32.0)) + Vec3::new(0.0, 0.0, Z_TEXT)),
                     MenuTextItem { layer, index: i as i32 },
@@ -201,11 +210,32 @@ pub fn menu_render_system(
 
 // バトルボックス補間
 pub fn update_box_size(mut box_res: ResMut<BattleBox>, time: Res<Time>) {
-    let speed = 15.0 * time.delta_secs();
-    box_res.current.min.x += (box_res.target.min.x - box_res.current.min.x) * speed;
-    box_res.current.min.y += (box_res.target.min.y - box_res.current.min.y) * speed;
-    box_res.current.max.x += (box_res.target.max.x - box_res.current.max.x) * speed;
-    box_res.current.max.y += (box_res.target.max.y - box_res.current.max.y) * speed;
+    let dt = time.delta_secs();
+
+    // スクリプト指定の補間
+    if let Some(mut tween) = box_res.rect_tween.take() {
+        let t = tween.advance(dt);
+        let rect = Rect::from_corners(tween.from.min.lerp(tween.to.min, t), tween.from.max.lerp(tween.to.max, t));
+        box_res.current = rect;
+        box_res.target = rect;
+        if !tween.finished() {
+            box_res.rect_tween = Some(tween);
+        }
+    } else {
+        let speed = 15.0 * dt;
+        box_res.current.min.x += (box_res.target.min.x - box_res.current.min.x) * speed;
+        box_res.current.min.y += (box_res.target.min.y - box_res.current.min.y) * speed;
+        box_res.current.max.x += (box_res.target.max.x - box_res.current.max.x) * speed;
+        box_res.current.max.y += (box_res.target.max.y - box_res.current.max.y) * speed;
+    }
+
+    if let Some(mut tween) = box_res.rotation_tween.take() {
+        let t = tween.advance(dt);
+        box_res.rotation = tween.from + (tween.to - tween.from) * t;
+        if !tween.finished() {
+            box_res.rotation_tween = Some(tween);
+        }
+    }
 }
 
 // バトルボックス描画
@@ -226,12 +256,52 @@ pub fn draw_battle_box(
     let center_x = bevy_left + width / 2.0;
     let center_y = bevy_bottom + height / 2.0;
 
-    if let Ok(mut t) = border.get_single_mut() { t.translation.x = center_x; t.translation.y = center_y; }
+    let rotation = Quat::from_rotation_z(box_res.rotation.to_radians());
+
+    if let Ok(mut t) = border.get_single_mut() { t.translation.x = center_x; t.translation.y = center_y; t.rotation = rotation; }
     if let Ok(mut s) = border_spr.get_single_mut() { s.custom_size = Some(Vec2::new(width + 10.0, height + 10.0)); }
-    if let Ok(mut t) = bg.get_single_mut() { t.translation.x = center_x; t.translation.y = center_y; }
+    if let Ok(mut t) = bg.get_single_mut() { t.translation.x = center_x; t.translation.y = center_y; t.rotation = rotation; }
     if let Ok(mut s) = bg_spr.get_single_mut() { s.custom_size = Some(Vec2::new(width, height)); }
 }
 
+// 足場描画
+pub fn draw_box_platforms(
+    mut commands: Commands,
+    box_res: Res<BattleBox>,
+    mut query: Query<(Entity, &PlatformVisual, &mut Transform, &mut Sprite)>,
+) {
+    let b = &box_res.current;
+    let center = Vec2::new(ORIGIN_X + b.center().x, ORIGIN_Y - b.center().y);
+    let half = b.half_size();
+    let rotation = Rot2::degrees(box_res.rotation);
+    let place = |index: usize| {
+        let rect = box_res.platforms[index].local_rect(half);
+        let position = center + rotation * rect.center();
+        (Transform::from_xyz(position.x, position.y, Z_PLATFORM).with_rotation(Quat::from_rotation_z(box_res.rotation.to_radians())), rect.size())
+    };
+
+    let mut shown = vec![false; box_res.platforms.len()];
+    for (entity, visual, mut transform, mut sprite) in query.iter_mut() {
+        if visual.index >= box_res.platforms.len() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let (next, size) = place(visual.index);
+        *transform = next;
+        sprite.custom_size = Some(size);
+        shown[visual.index] = true;
+    }
+    for index in (0..box_res.platforms.len()).filter(|index| !shown[*index]) {
+        let (transform, size) = place(index);
+        commands.spawn((
+            Sprite { color: Color::srgb(0.0, 0.75, 0.0), custom_size: Some(size), ..default() },
+            transform,
+            PlatformVisual { index },
+            Cleanup,
+        ));
+    }
+}
+
 // HP表示更新
 pub fn draw_ui_status(
     player_state: Res<PlayerState>,
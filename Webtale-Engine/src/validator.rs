@@ -0,0 +1,593 @@
+// プロジェクト検証
+use std::collections::HashSet;
+use std::path::Path;
+use evalexpr::build_operator_tree;
+use rustpython_vm::builtins::{PyBaseExceptionRef, PyDictRef};
+use rustpython_vm::compiler::Mode;
+use rustpython_vm::import::import_codeobj;
+use rustpython_vm::{PyObjectRef, VirtualMachine};
+use crate::project;
+use crate::project_archive;
+use crate::project_fs;
+use crate::python_scripts;
+use crate::python_utils::{callable_arg_count, read_option_string, read_option_vec_string};
+use crate::constants::MAX_ENEMIES;
+use crate::resources::{AttackBarStyle, DanmakuWave, ItemKind, ProjectManifest, PythonRuntime};
+use crate::systems::combat::{parse_bullet_color, parse_hitbox, parse_rust_sim_spec, parse_wave_init, spawn_results};
+use crate::systems::phase;
+use crate::systems::setup::{default_combat_state, default_enemy_state};
+
+// 重大度
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+// 検証項目
+pub struct ValidationIssue {
+    pub severity: Severity,
+    pub file: String,
+    pub message: String,
+}
+
+// 検証結果
+#[derive(Default)]
+pub struct ValidationReport {
+    pub project: String,
+    pub checked_scripts: usize,
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    fn error(&mut self, file: &str, message: impl Into<String>) {
+        self.issues.push(ValidationIssue { severity: Severity::Error, file: file.to_string(), message: message.into() });
+    }
+
+    fn warning(&mut self, file: &str, message: impl Into<String>) {
+        self.issues.push(ValidationIssue { severity: Severity::Warning, file: file.to_string(), message: message.into() });
+    }
+
+    pub fn error_count(&self) -> usize {
+        self.issues.iter().filter(|issue| issue.severity == Severity::Error).count()
+    }
+
+    pub fn warning_count(&self) -> usize {
+        self.issues.iter().filter(|issue| issue.severity == Severity::Warning).count()
+    }
+
+    // 結果出力
+    pub fn print(&self) {
+        println!();
+        println!("Validation report: {}", self.project);
+        println!("  scripts checked: {}", self.checked_scripts);
+        for issue in &self.issues {
+            let label = match issue.severity {
+                Severity::Error => "ERROR",
+                Severity::Warning => "WARNING",
+            };
+            println!("  {:<7} {}: {}", label, issue.file, issue.message);
+        }
+        println!("Result: {} errors, {} warnings", self.error_count(), self.warning_count());
+    }
+}
+
+// 検証対象引数
+pub fn validate_target_from_args() -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--validate" {
+            return Some(args.next().unwrap_or_default());
+        }
+        if let Some(value) = arg.strip_prefix("--validate=") {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+// 検証実行
+pub fn run(target: &str) -> i32 {
+    let project_name = if project_archive::is_archive_path(target) {
+        match project_archive::mount_archive_file(target) {
+            Ok(project_name) => project_name,
+            Err(message) => {
+                println!("Error: {}", message);
+                return 2;
+            }
+        }
+    } else {
+        target.to_string()
+    };
+
+    let python_runtime = PythonRuntime::default();
+    let report = validate_project(&python_runtime, &project_name);
+    report.print();
+    if report.error_count() > 0 { 1 } else { 0 }
+}
+
+// 例外要約
+fn exception_text(vm: &VirtualMachine, err: &PyBaseExceptionRef) -> String {
+    let mut text = String::new();
+    let _ = vm.write_exception(&mut text, err);
+    text.trim_end().lines().last().unwrap_or("exception").trim().to_string()
+}
+
+// アセット存在確認
+fn asset_exists(project_name: &str, manifest: &ProjectManifest, path: &str) -> bool {
+    let resolved = project::resolve_asset_path(project_name, manifest, path);
+    if let Some(project_path) = resolved.strip_prefix(&format!("{}://", project::PROJECTS_ASSET_SOURCE)) {
+        return match project_path.split_once('/') {
+            Some((project_name, relative_path)) => project_fs::file_exists(project_name, relative_path),
+            None => false,
+        };
+    }
+    if resolved.contains("://") {
+        return true;
+    }
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("assets").join(&resolved).is_file()
+}
+
+// モジュール読み込み
+fn import_module(vm: &VirtualMachine, report: &mut ValidationReport, project_name: &str, file: &str, module_name: &str) -> Option<PyObjectRef> {
+    let code = match python_scripts::get_project_script(project_name, file) {
+        Some(code) => code,
+        None => {
+            report.error(file, "file missing");
+            return None;
+        }
+    };
+    let code_obj = match vm.compile(&code, Mode::Exec, file.to_string()) {
+        Ok(code_obj) => code_obj,
+        Err(_) => return None,
+    };
+    match import_codeobj(vm, module_name, code_obj, true) {
+        Ok(module) => Some(module),
+        Err(err) => {
+            report.error(file, format!("module failed {}", exception_text(vm, &err)));
+            None
+        }
+    }
+}
+
+// 関数呼び出し
+fn call_function(vm: &VirtualMachine, report: &mut ValidationReport, file: &str, module: &PyObjectRef, func_name: &str, args: Vec<PyObjectRef>) -> Option<PyObjectRef> {
+    let name = vm.ctx.new_str(func_name);
+    let func = match module.get_attr(&name, vm) {
+        Ok(func) => func,
+        Err(_) => {
+            report.error(file, format!("missing {}", func_name));
+            return None;
+        }
+    };
+    match func.call(args, vm) {
+        Ok(result) => Some(result),
+        Err(err) => {
+            report.error(file, format!("{} failed {}", func_name, exception_text(vm, &err)));
+            None
+        }
+    }
+}
+
+// 辞書結果取得
+fn call_dict(vm: &VirtualMachine, report: &mut ValidationReport, file: &str, module: &PyObjectRef, func_name: &str, args: Vec<PyObjectRef>) -> Option<PyDictRef> {
+    let result = call_function(vm, report, file, module, func_name, args)?;
+    match result.try_into_value::<PyDictRef>(vm) {
+        Ok(dict) => Some(dict),
+        Err(_) => {
+            report.error(file, format!("{} must return dict", func_name));
+            None
+        }
+    }
+}
+
+// sys.modules登録
+fn register_module(vm: &VirtualMachine, module_name: &str, module: &PyObjectRef) -> bool {
+    vm.import("sys", 0)
+        .and_then(|sys| sys.get_attr("modules", vm))
+        .and_then(|modules| modules.set_item(module_name, module.clone(), vm))
+        .is_ok()
+}
+
+// プロジェクト検証
+pub fn validate_project(python_runtime: &PythonRuntime, project_name: &str) -> ValidationReport {
+    let mut report = ValidationReport { project: project_name.to_string(), ..Default::default() };
+    if !python_scripts::project_exists(project_name) {
+        report.error(project_name, "project missing");
+        return report;
+    }
+
+    let manifest = match project::load_project_manifest(python_runtime, project_name) {
+        Ok(manifest) => manifest,
+        Err(message) => {
+            report.error("project.py", message);
+            ProjectManifest::default_layout(project_name)
+        }
+    };
+
+    let files = project_fs::list_all_files(project_name);
+    let phase_names: HashSet<String> = python_scripts::list_phase_names(project_name).into_iter().collect();
+    let danmaku_names: Vec<String> = project_fs::list_files(project_name, "danmaku")
+        .into_iter()
+        .filter_map(|name| name.strip_suffix(".py").map(|stem| stem.to_string()))
+        .filter(|stem| stem != "api")
+        .collect();
+    let tachie_names: Vec<String> = project_fs::list_files(project_name, "tachie")
+        .into_iter()
+        .filter_map(|name| name.strip_suffix(".py").map(|stem| stem.to_string()))
+        .collect();
+
+    python_runtime.interpreter.enter(|vm| {
+        // 構文検査
+        for file in files.iter().filter(|file| file.ends_with(".py")) {
+            report.checked_scripts += 1;
+            let code = match project_fs::read_to_string(project_name, file) {
+                Some(code) => code,
+                None => {
+                    report.error(file, "not readable as utf-8");
+                    continue;
+                }
+            };
+            if let Err(err) = vm.compile(&code, Mode::Exec, file.clone()) {
+                report.error(file, format!("compile {}", err));
+            }
+        }
+
+        // アイテム
+        let mut item_names: HashSet<String> = HashSet::new();
+        let mut equipment_names: HashSet<String> = HashSet::new();
+        let item_file = manifest.item_script.clone();
+        if let Some(module) = import_module(vm, &mut report, project_name, &item_file, "validate_item") {
+            if let Some(dict) = call_dict(vm, &mut report, &item_file, &module, "getItemData", vec![]) {
+                for (key, value) in &dict {
+                    let item_name: String = match key.try_into_value(vm) {
+                        Ok(name) => name,
+                        Err(_) => {
+                            report.error(&item_file, "getItemData key must be str");
+                            continue;
+                        }
+                    };
+                    match value.try_into_value::<PyDictRef>(vm) {
+                        Ok(data) => {
+                            let kind = read_option_string(vm, &data, "kind", "itemData", false);
+                            match kind.as_deref().map(ItemKind::from_name) {
+                                Some(Some(kind)) if kind.is_equipment() => {
+                                    equipment_names.insert(item_name.clone());
+                                }
+                                Some(None) => report.error(&item_file, format!("item {} kind must be consumable, weapon or armor", item_name)),
+                                _ => {}
+                            }
+                            if let Some(style) = read_option_string(vm, &data, "barStyle", "itemData", false) {
+                                if AttackBarStyle::from_name(&style).is_none() {
+                                    report.error(&item_file, format!("item {} barStyle {} invalid", item_name, style));
+                                }
+                            }
+                            if let Ok(Some(callback)) = data.get_item_opt("use", vm) {
+                                let callable = match callback.clone().try_into_value::<String>(vm) {
+                                    Ok(name) => module.get_attr(&vm.ctx.new_str(name), vm).is_ok_and(|func| func.is_callable()),
+                                    Err(_) => callback.is_callable(),
+                                };
+                                if !callable {
+                                    report.error(&item_file, format!("item {} use must be function or function name", item_name));
+                                }
+                            }
+                            if let Ok(Some(consume)) = data.get_item_opt("consume", vm) {
+                                if consume.try_into_value::<bool>(vm).is_err() {
+                                    report.error(&item_file, format!("item {} consume must be bool", item_name));
+                                }
+                            }
+                        }
+                        Err(_) => report.error(&item_file, format!("item {} must be dict", item_name)),
+                    }
+                    item_names.insert(item_name);
+                }
+            }
+        }
+
+        // プレイヤー
+        let player_file = manifest.player_script.clone();
+        if let Some(module) = import_module(vm, &mut report, project_name, &player_file, "validate_player") {
+            if let Some(dict) = call_dict(vm, &mut report, &player_file, &module, "getPlayerStatus", vec![]) {
+                for key in ["inventory", "equippedItems"] {
+                    for item in read_option_vec_string(vm, &dict, key, "playerStatus", false).unwrap_or_default() {
+                        if !item_names.contains(&item) {
+                            report.error(&player_file, format!("{} item {} missing in ItemDictionary", key, item));
+                        }
+                    }
+                }
+                for item in read_option_vec_string(vm, &dict, "equippedItems", "playerStatus", false).unwrap_or_default() {
+                    if item_names.contains(&item) && !equipment_names.contains(&item) {
+                        report.error(&player_file, format!("equippedItems item {} is not weapon or armor", item));
+                    }
+                }
+                if let Ok(Some(value)) = dict.get_item_opt("lvTable", vm) {
+                    match value.try_into_value::<Vec<PyDictRef>>(vm) {
+                        Ok(entries) if entries.is_empty() => report.error(&player_file, "lvTable must not be empty"),
+                        Ok(entries) => {
+                            let mut exp_values = Vec::new();
+                            for (index, entry) in entries.iter().enumerate() {
+                                for key in ["exp", "maxHp", "attack", "defense"] {
+                                    if !matches!(entry.get_item_opt(key, vm), Ok(Some(_))) {
+                                        report.error(&player_file, format!("lvTable LV{} missing {}", index + 1, key));
+                                    }
+                                }
+                                if let Ok(Some(exp)) = entry.get_item_opt("exp", vm) {
+                                    match exp.try_into_value::<i32>(vm) {
+                                        Ok(exp) => exp_values.push(exp),
+                                        Err(_) => report.error(&player_file, format!("lvTable LV{} exp must be int", index + 1)),
+                                    }
+                                }
+                            }
+                            if exp_values.windows(2).any(|pair| pair[0] > pair[1]) {
+                                report.error(&player_file, "lvTable exp must be ascending");
+                            }
+                        }
+                        Err(_) => report.error(&player_file, "lvTable must be list of dict"),
+                    }
+                }
+            }
+        }
+
+        // 敵
+        let mut enemy_count = 0;
+        for (script_index, enemy_file) in manifest.enemy_scripts().into_iter().enumerate() {
+            let Some(module) = import_module(vm, &mut report, project_name, &enemy_file, &format!("validate_enemy_{}", script_index)) else { continue };
+            let Some(result) = call_function(vm, &mut report, &enemy_file, &module, "getEnemyStatus", vec![]) else { continue };
+            let dicts = match result.clone().try_into_value::<PyDictRef>(vm) {
+                Ok(dict) => vec![dict],
+                Err(_) => match result.try_into_value::<Vec<PyDictRef>>(vm) {
+                    Ok(dicts) => dicts,
+                    Err(_) => {
+                        report.error(&enemy_file, "getEnemyStatus must return dict or list of dict");
+                        continue;
+                    }
+                },
+            };
+            enemy_count += dicts.len();
+            for dict in dicts {
+                match read_option_vec_string(vm, &dict, "attackPatterns", "enemyStatus", false) {
+                    Some(attacks) if !attacks.is_empty() => {
+                        for attack in attacks {
+                            if !danmaku_names.contains(&attack) {
+                                report.error(&enemy_file, format!("attack pattern {} missing danmaku/{}.py", attack, attack));
+                            }
+                        }
+                    }
+                    _ => report.warning(&enemy_file, "attackPatterns empty"),
+                }
+                if let Some(phase_script) = read_option_string(vm, &dict, "phaseScript", "enemyStatus", false) {
+                    if !phase_script.is_empty() && !phase_names.contains(&phase_script) {
+                        report.error(&enemy_file, format!("phaseScript {} missing phases/{}.py", phase_script, phase_script));
+                    }
+                }
+                if let Some(tachie_script) = read_option_string(vm, &dict, "tachieScript", "enemyStatus", false) {
+                    if !tachie_script.is_empty() && !tachie_names.contains(&tachie_script) {
+                        report.error(&enemy_file, format!("tachieScript {} missing tachie/{}.py", tachie_script, tachie_script));
+                    }
+                }
+                let act_commands = read_option_vec_string(vm, &dict, "actCommands", "enemyStatus", false).unwrap_or_default();
+                if let Ok(Some(spare_acts)) = dict.get_item_opt("spareActs", vm) {
+                    match spare_acts.try_into_value::<PyDictRef>(vm) {
+                        Ok(spare_acts) => {
+                            for (key, _) in &spare_acts {
+                                let command = key.try_into_value::<String>(vm).unwrap_or_default();
+                                if !act_commands.contains(&command) {
+                                    report.warning(&enemy_file, format!("spareActs {} not in actCommands", command));
+                                }
+                            }
+                        }
+                        Err(_) => report.error(&enemy_file, "spareActs must be dict"),
+                    }
+                }
+                for key in ["bodyTexture", "headTexture"] {
+                    if let Some(texture) = read_option_string(vm, &dict, key, "enemyStatus", false) {
+                        if !texture.is_empty() && !asset_exists(project_name, &manifest, &texture) {
+                            report.error(&enemy_file, format!("{} asset missing {}", key, texture));
+                        }
+                    }
+                }
+            }
+        }
+        if enemy_count > MAX_ENEMIES {
+            report.error("project.py", format!("encounter has {} enemies (max {})", enemy_count, MAX_ENEMIES));
+        }
+
+        // ダメージ計算式
+        if let Some(damage_file) = manifest.damage_script.clone() {
+            if let Some(module) = import_module(vm, &mut report, project_name, &damage_file, "validate_damage") {
+                if module.get_attr("damageFormula", vm).is_err() {
+                    report.error(&damage_file, "missing damageFormula");
+                }
+            }
+        }
+        if let Some(formula) = &manifest.damage_formula {
+            if let Err(err) = build_operator_tree(formula) {
+                report.error("project.py", format!("damage formula {:?}", err));
+            }
+        }
+
+        // 立ち絵
+        for tachie_name in &tachie_names {
+            let file = format!("tachie/{}.py", tachie_name);
+            if let Some(module) = import_module(vm, &mut report, project_name, &file, &format!("validate_tachie_{}", tachie_name)) {
+                call_dict(vm, &mut report, &file, &module, "getTachieData", vec![]);
+            }
+        }
+
+        // フェーズ
+        if !manifest.initial_phase.is_empty() && !phase_names.contains(&manifest.initial_phase) {
+            report.error("project.py", format!("initialPhase {} missing phases/{}.py", manifest.initial_phase, manifest.initial_phase));
+        }
+        if !phase_names.is_empty() && !project_fs::file_exists(project_name, "phases/phase_api.py") {
+            report.error("phases/phase_api.py", "file missing");
+        }
+        let mut sorted_phases: Vec<&String> = phase_names.iter().collect();
+        sorted_phases.sort();
+        let mut enemy_state = default_enemy_state();
+        enemy_state.phase_turn = 1;
+        let mut combat_state = default_combat_state();
+        combat_state.turn_count = 1;
+        for phase_name in sorted_phases {
+            let file = format!("phases/{}.py", phase_name);
+            let api_module = match import_module(vm, &mut report, project_name, "phases/phase_api.py", "phase_api") {
+                Some(module) => module,
+                None => break,
+            };
+            if !register_module(vm, "phase_api", &api_module) {
+                report.error("phases/phase_api.py", "register failed");
+                break;
+            }
+            let context = phase::build_phase_context(vm, &enemy_state, &combat_state, phase_name, "start");
+            call_function(vm, &mut report, "phases/phase_api.py", &api_module, "reset", vec![context.clone().into()]);
+            let module = match import_module(vm, &mut report, project_name, &file, phase_name) {
+                Some(module) => module,
+                None => continue,
+            };
+            let result = match call_function(vm, &mut report, &file, &module, "update", vec![context.into()]) {
+                Some(result) => result,
+                None => continue,
+            };
+            let state = match result.try_into_value::<PyDictRef>(vm) {
+                Ok(dict) => dict,
+                Err(_) => match call_dict(vm, &mut report, "phases/phase_api.py", &api_module, "getState", vec![]) {
+                    Some(dict) => dict,
+                    None => continue,
+                },
+            };
+            if let Some(next_phase) = read_option_string(vm, &state, "nextPhase", "phase", false) {
+                if !next_phase.is_empty() && !phase_names.contains(&next_phase) {
+                    report.error(&file, format!("nextPhase {} missing phases/{}.py", next_phase, next_phase));
+                }
+            }
+            for attack in read_option_vec_string(vm, &state, "attackPatterns", "phase", false).unwrap_or_default() {
+                if !danmaku_names.contains(&attack) {
+                    report.error(&file, format!("attack pattern {} missing danmaku/{}.py", attack, attack));
+                }
+            }
+            if let Some(texture) = read_option_string(vm, &state, "bubbleTexture", "phase", false) {
+                let resolved = phase::resolve_bubble_texture_name(&texture);
+                if !asset_exists(project_name, &manifest, &resolved) {
+                    report.error(&file, format!("bubbleTexture {} unknown", texture));
+                }
+            }
+        }
+
+        // 弾幕
+        if !danmaku_names.is_empty() && !project_fs::file_exists(project_name, "danmaku/api.py") {
+            report.error("danmaku/api.py", "file missing");
+        }
+        for danmaku_name in &danmaku_names {
+            let file = format!("danmaku/{}.py", danmaku_name);
+            let api_module = match import_module(vm, &mut report, project_name, "danmaku/api.py", "api") {
+                Some(module) => module,
+                None => break,
+            };
+            if !register_module(vm, "api", &api_module) {
+                report.error("danmaku/api.py", "register failed");
+                break;
+            }
+            let module = match import_module(vm, &mut report, project_name, &file, danmaku_name) {
+                Some(module) => module,
+                None => continue,
+            };
+            let init_data = match call_dict(vm, &mut report, &file, &module, "init", vec![]) {
+                Some(dict) => dict,
+                None => continue,
+            };
+            match read_option_string(vm, &init_data, "textureWait", "danmaku", false) {
+                Some(texture) => {
+                    if !asset_exists(project_name, &manifest, &texture) {
+                        report.error(&file, format!("textureWait asset missing {}", texture));
+                    }
+                }
+                None => report.error(&file, "init textureWait missing"),
+            }
+            let has_rust_sim = match init_data.get_item_opt("rustSim", vm) {
+                Ok(Some(value)) => {
+                    match value.try_into_value::<PyDictRef>(vm) {
+                        Ok(rust_sim) => {
+                            let mut issues = Vec::new();
+                            parse_rust_sim_spec(vm, &rust_sim, &mut issues);
+                            for issue in issues {
+                                report.error(&file, issue);
+                            }
+                        }
+                        Err(_) => report.error(&file, "rustSim must be dict"),
+                    }
+                    true
+                }
+                _ => false,
+            };
+            let mut wave = DanmakuWave::default();
+            let mut issues = Vec::new();
+            parse_wave_init(vm, &module, &init_data, &mut wave, &mut issues);
+            for issue in issues {
+                report.error(&file, issue);
+            }
+            // 旧形式は引数なし
+            let spawn_args = match module.get_attr("spawn", vm) {
+                Ok(func) if callable_arg_count(vm, &func) == Some(0) => vec![],
+                _ => {
+                    let params = vm.ctx.new_dict();
+                    for (key, value) in [("index", 0), ("count", 1), ("burst", 0)] {
+                        let _ = params.set_item(key, vm.new_pyobj(value), vm);
+                    }
+                    for key in ["angle", "t", "x", "y", "centerX", "centerY"] {
+                        let _ = params.set_item(key, vm.new_pyobj(0.0), vm);
+                    }
+                    vec![params.into()]
+                }
+            };
+            let spawn_result = match call_function(vm, &mut report, &file, &module, "spawn", spawn_args) {
+                Some(result) => result,
+                None => continue,
+            };
+            for bullet in spawn_results(vm, spawn_result) {
+                let (hitbox, color, on_collide) = if has_rust_sim {
+                    match bullet.try_into_value::<PyDictRef>(vm) {
+                        Ok(spawn_dict) => {
+                            let vars_dict = spawn_dict.get_item_opt("vars", vm).ok().flatten().and_then(|value| value.try_into_value::<PyDictRef>(vm).ok());
+                            let color = spawn_dict
+                                .get_item_opt("color", vm)
+                                .ok()
+                                .flatten()
+                                .or_else(|| vars_dict.and_then(|dict| dict.get_item_opt("color", vm).ok().flatten()));
+                            let hitbox = spawn_dict.get_item_opt("hitbox", vm).ok().flatten();
+                            (hitbox, color, spawn_dict.get_item_opt("onCollide", vm).ok().flatten())
+                        }
+                        Err(_) => {
+                            report.error(&file, "rustSim spawn must return dict");
+                            (None, None, None)
+                        }
+                    }
+                } else {
+                    for attr in ["setPos", "damage"] {
+                        if bullet.get_attr(attr, vm).is_err() {
+                            report.error(&file, format!("spawn result missing {}", attr));
+                        }
+                    }
+                    (
+                        bullet.get_attr("hitbox", vm).ok().filter(|value| !vm.is_none(value)),
+                        bullet.get_attr("color", vm).ok().filter(|value| !vm.is_none(value)),
+                        bullet.get_attr("onCollide", vm).ok(),
+                    )
+                };
+                if on_collide.is_some_and(|value| !vm.is_none(&value) && !value.is_callable()) {
+                    report.error(&file, "bullet onCollide must be callable");
+                }
+                let mut issues = Vec::new();
+                if let Some(hitbox) = hitbox {
+                    parse_hitbox(vm, &hitbox, &mut issues);
+                }
+                if let Some(color) = color {
+                    parse_bullet_color(vm, &color, &mut issues);
+                }
+                for issue in issues {
+                    report.error(&file, issue);
+                }
+            }
+        }
+    });
+
+    report
+}